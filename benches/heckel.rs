@@ -0,0 +1,124 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use heckel_diff::algorithm::{Algorithm, DiffAlgorithm};
+use heckel_diff::{diff_slices, Heckel};
+use std::hint::black_box;
+
+/// Generate a log-like file of `len` lines, mostly unique with a sprinkling
+/// of repeated lines.
+fn log(len: usize) -> Vec<String> {
+    (0..len)
+        .map(|i| match i % 10 {
+            0 => String::from("---"),
+            _ => format!(
+                "{i:08} INFO request {} handled in {}ms",
+                i * 7919 % 104729,
+                i % 97
+            ),
+        })
+        .collect()
+}
+
+/// Edit roughly one line in fifty, and move a few blocks around.
+fn edit(old: &[String]) -> Vec<String> {
+    let mut new: Vec<String> = old
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| i % 53 != 0)
+        .map(|(i, line)| match i % 47 {
+            0 => format!("{line} (retried)"),
+            _ => line.clone(),
+        })
+        .collect();
+    for at in (0..new.len() / 2).step_by(new.len() / 8 + 1) {
+        let block: Vec<_> = new.drain(at..at + 20.min(new.len() - at)).collect();
+        let to = new.len() - at;
+        new.splice(to..to, block);
+    }
+    new
+}
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("diff_slices");
+    for len in [1_000, 100_000, 1_000_000] {
+        let old = log(len);
+        let new = edit(&old);
+        group.throughput(Throughput::Elements(len as u64));
+        group.sample_size(10);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(len),
+            &(old, new),
+            |b, (old, new)| b.iter(|| diff_slices(black_box(old), black_box(new))),
+        );
+    }
+    group.finish();
+}
+
+/// Some of this crate's own source files, as real code with the usual
+/// repeated lines: braces, blank lines and attributes.
+const SOURCES: [&str; 4] = [
+    include_str!("../src/lib.rs"),
+    include_str!("../src/html.rs"),
+    include_str!("../src/side_by_side.rs"),
+    include_str!("../src/unified.rs"),
+];
+
+/// Edit source code the way a larger change might: rewrite one line in
+/// seven, delete a few, and add a few blocks ending in the same lines as
+/// the code around them.
+fn edit_source(old: &str) -> Vec<&str> {
+    let mut new = Vec::new();
+    for (i, line) in old.lines().enumerate() {
+        match (i % 41, i % 37) {
+            (0, _) => continue,
+            (_, 0) => new.extend(["        if done {", "            break;", "        }", ""]),
+            _ => {}
+        }
+        new.push(match i % 7 {
+            0 => "        // changed",
+            _ => line,
+        });
+    }
+    new
+}
+
+fn bench_sources(c: &mut Criterion) {
+    let algorithms = [
+        ("plain", Algorithm::default()),
+        (
+            "anchored",
+            Algorithm::Heckel(Heckel {
+                anchor_depth: 8,
+                ..Default::default()
+            }),
+        ),
+        (
+            "filled",
+            Algorithm::Heckel(Heckel {
+                fill_gaps: true,
+                ..Default::default()
+            }),
+        ),
+        ("myers", Algorithm::Myers),
+        ("patience", Algorithm::Patience),
+        ("histogram", Algorithm::Histogram),
+    ];
+
+    let mut group = c.benchmark_group("sources");
+    for (name, algorithm) in algorithms {
+        let pairs: Vec<(Vec<&str>, Vec<&str>)> = SOURCES
+            .iter()
+            .map(|source| (source.lines().collect(), edit_source(source)))
+            .collect();
+        group.bench_with_input(BenchmarkId::from_parameter(name), &pairs, |b, pairs| {
+            b.iter(|| {
+                for (old, new) in pairs {
+                    algorithm.diff(black_box(old), black_box(new));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench, bench_sources);
+criterion_main!(benches);
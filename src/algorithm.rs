@@ -0,0 +1,359 @@
+//! Diff algorithms other than Heckel's, behind a common trait, for comparing
+//! their results or choosing one per kind of file.
+//!
+//! Every algorithm produces the same [`Diff`], so anything built on top of
+//! it (hunks, patches, the output formats) works with any of them. Only
+//! [`Heckel`] finds moved blocks; the others match lines in order, so their
+//! diffs are made up of equal, deleted and inserted lines alone.
+//!
+//! ```
+//! use heckel_diff::algorithm::{Algorithm, DiffAlgorithm};
+//! use heckel_diff::Op;
+//!
+//! let old = ["fn a() {", "}", "", "fn b() {", "}"];
+//! let new = ["fn b() {", "}", "", "fn a() {", "}"];
+//! let moves = |algorithm: Algorithm| {
+//!     let diff = algorithm.diff(&old, &new);
+//!     diff.ops().iter().any(|op| matches!(op, Op::Move { .. }))
+//! };
+//! assert!(moves(Algorithm::default()));
+//! assert!(!moves(Algorithm::Myers));
+//! assert!(!moves(Algorithm::Patience));
+//! assert!(!moves(Algorithm::Histogram));
+//! ```
+
+use crate::{myers, Diff, Heckel};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+
+/// An algorithm for diffing two sequences.
+pub trait DiffAlgorithm {
+    /// Diff the old sequence `old` against the new sequence `new`, returning
+    /// an edit script that transforms one into the other.
+    fn diff<T: Hash + Eq>(&self, old: &[T], new: &[T]) -> Diff;
+}
+
+impl DiffAlgorithm for Heckel {
+    fn diff<T: Hash + Eq>(&self, old: &[T], new: &[T]) -> Diff {
+        self.diff_with_hasher(old, new, RandomState::new())
+    }
+}
+
+/// Myers' O(ND) algorithm, which finds a longest common subsequence, and so
+/// the smallest possible diff. It's what GNU diff and git use by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Myers;
+
+impl DiffAlgorithm for Myers {
+    fn diff<T: Hash + Eq>(&self, old: &[T], new: &[T]) -> Diff {
+        let (old, new) = intern(old, new);
+        references(old.len(), new.len(), myers::lcs(&old, &new))
+    }
+}
+
+/// Bram Cohen's patience diff, as in `git diff --patience`: match the
+/// longest increasing sequence of lines that are unique in both files, then
+/// recurse between them, falling back to Myers' algorithm where there are
+/// no unique lines. Tends to line up diffs with the structure of the code
+/// rather than with braces and blank lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Patience;
+
+impl DiffAlgorithm for Patience {
+    fn diff<T: Hash + Eq>(&self, old: &[T], new: &[T]) -> Diff {
+        let (old, new) = intern(old, new);
+        let mut matches = Vec::new();
+        patience(&old, 0..old.len(), &new, 0..new.len(), &mut matches);
+        references(old.len(), new.len(), matches)
+    }
+}
+
+/// git's histogram diff, as in `git diff --histogram`: an extension of
+/// patience diff that matches the longest common region containing the
+/// line that occurs least often in the old file, so it still finds
+/// anchors when no line is unique. Regions made up of lines that occur
+/// more than [`Histogram::MAX_CHAIN`] times fall back to Myers' algorithm.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Histogram;
+
+impl Histogram {
+    /// The most occurrences in the old file a line can have and still be
+    /// considered as an anchor, like git.
+    pub const MAX_CHAIN: usize = 64;
+}
+
+impl DiffAlgorithm for Histogram {
+    fn diff<T: Hash + Eq>(&self, old: &[T], new: &[T]) -> Diff {
+        let (old, new) = intern(old, new);
+        let mut matches = Vec::new();
+        histogram(&old, 0..old.len(), &new, 0..new.len(), &mut matches);
+        references(old.len(), new.len(), matches)
+    }
+}
+
+/// One of the built-in algorithms, for choosing one at run time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Heckel's algorithm, with the given options.
+    Heckel(Heckel),
+
+    /// See [`Myers`].
+    Myers,
+
+    /// See [`Patience`].
+    Patience,
+
+    /// See [`Histogram`].
+    Histogram,
+}
+
+impl Default for Algorithm {
+    fn default() -> Self {
+        Self::Heckel(Heckel::default())
+    }
+}
+
+impl DiffAlgorithm for Algorithm {
+    fn diff<T: Hash + Eq>(&self, old: &[T], new: &[T]) -> Diff {
+        match self {
+            Self::Heckel(heckel) => DiffAlgorithm::diff(heckel, old, new),
+            Self::Myers => Myers.diff(old, new),
+            Self::Patience => Patience.diff(old, new),
+            Self::Histogram => Histogram.diff(old, new),
+        }
+    }
+}
+
+/// Replace each element of `old` and `new` by a number identifying it, so
+/// the algorithms compare numbers rather than lines.
+fn intern<'a, T: Hash + Eq>(old: &'a [T], new: &'a [T]) -> (Vec<usize>, Vec<usize>) {
+    let mut ids: HashMap<&'a T, usize> = HashMap::with_capacity(old.len());
+    let mut intern = |lines: &'a [T]| -> Vec<usize> {
+        lines
+            .iter()
+            .map(|line| {
+                let next = ids.len();
+                *ids.entry(line).or_insert(next)
+            })
+            .collect()
+    };
+    let old = intern(old);
+    (old, intern(new))
+}
+
+/// Build the diff of a file `old_len` lines long against one `new_len`
+/// lines long from the pairs of matching lines.
+fn references(old_len: usize, new_len: usize, matches: Vec<(usize, usize)>) -> Diff {
+    let mut new_refs = vec![None; new_len];
+    for (i, j) in matches {
+        new_refs[j] = Some(i);
+    }
+    Diff::from_references(old_len, &new_refs)
+}
+
+/// Match the common prefix of `a[a_range]` and `b[b_range]` and strip it
+/// from the ranges, returning the length of their common suffix, which the
+/// caller is to match once it has matched everything before it.
+fn strip(
+    a: &[usize],
+    a_range: &mut Range<usize>,
+    b: &[usize],
+    b_range: &mut Range<usize>,
+    matches: &mut Vec<(usize, usize)>,
+) -> usize {
+    while a_range.start < a_range.end
+        && b_range.start < b_range.end
+        && a[a_range.start] == b[b_range.start]
+    {
+        matches.push((a_range.start, b_range.start));
+        a_range.start += 1;
+        b_range.start += 1;
+    }
+    let mut suffix = 0;
+    while a_range.start < a_range.end
+        && b_range.start < b_range.end
+        && a[a_range.end - 1] == b[b_range.end - 1]
+    {
+        a_range.end -= 1;
+        b_range.end -= 1;
+        suffix += 1;
+    }
+    suffix
+}
+
+/// Match up `a[a_range]` and `b[b_range]` with Myers' algorithm.
+fn fall_back(
+    a: &[usize],
+    a_range: Range<usize>,
+    b: &[usize],
+    b_range: Range<usize>,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    let (a_start, b_start) = (a_range.start, b_range.start);
+    matches.extend(
+        myers::lcs(&a[a_range], &b[b_range])
+            .into_iter()
+            .map(|(i, j)| (a_start + i, b_start + j)),
+    );
+}
+
+fn patience(
+    a: &[usize],
+    mut a_range: Range<usize>,
+    b: &[usize],
+    mut b_range: Range<usize>,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    let suffix = strip(a, &mut a_range, b, &mut b_range, matches);
+    if !a_range.is_empty() && !b_range.is_empty() {
+        // the lines unique in both ranges, in the order they appear in b
+        let mut counts: HashMap<usize, (usize, usize, usize)> = HashMap::new();
+        for i in a_range.clone() {
+            let count = counts.entry(a[i]).or_default();
+            count.0 += 1;
+            count.2 = i;
+        }
+        for j in b_range.clone() {
+            if let Some(count) = counts.get_mut(&b[j]) {
+                count.1 += 1;
+            }
+        }
+        let unique: Vec<(usize, usize)> = b_range
+            .clone()
+            .filter_map(|j| match counts.get(&b[j]) {
+                Some(&(1, 1, i)) => Some((i, j)),
+                _ => None,
+            })
+            .collect();
+
+        let anchors = longest_increasing(&unique);
+        if anchors.is_empty() {
+            fall_back(a, a_range.clone(), b, b_range.clone(), matches);
+        } else {
+            let (mut i, mut j) = (a_range.start, b_range.start);
+            for (next_i, next_j) in anchors {
+                patience(a, i..next_i, b, j..next_j, matches);
+                matches.push((next_i, next_j));
+                (i, j) = (next_i + 1, next_j + 1);
+            }
+            patience(a, i..a_range.end, b, j..b_range.end, matches);
+        }
+    }
+    matches.extend((0..suffix).map(|k| (a_range.end + k, b_range.end + k)));
+}
+
+/// The longest subsequence of `pairs` whose first elements are increasing,
+/// found by patience sorting.
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // the index into pairs of the top card of each pile, and for each card,
+    // the top card of the previous pile when it was placed
+    let mut piles: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = Vec::with_capacity(pairs.len());
+    for (k, &(i, _)) in pairs.iter().enumerate() {
+        let pile = piles.partition_point(|&top| pairs[top].0 < i);
+        previous.push(pile.checked_sub(1).map(|pile| piles[pile]));
+        match piles.get_mut(pile) {
+            Some(top) => *top = k,
+            None => piles.push(k),
+        }
+    }
+
+    let mut sequence = Vec::with_capacity(piles.len());
+    let mut card = piles.last().copied();
+    while let Some(k) = card {
+        sequence.push(pairs[k]);
+        card = previous[k];
+    }
+    sequence.reverse();
+    sequence
+}
+
+fn histogram(
+    a: &[usize],
+    mut a_range: Range<usize>,
+    b: &[usize],
+    mut b_range: Range<usize>,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    let suffix = strip(a, &mut a_range, b, &mut b_range, matches);
+    if !a_range.is_empty() && !b_range.is_empty() {
+        match common_region(a, a_range.clone(), b, b_range.clone()) {
+            Some((region_a, region_b)) => {
+                histogram(
+                    a,
+                    a_range.start..region_a.start,
+                    b,
+                    b_range.start..region_b.start,
+                    matches,
+                );
+                matches.extend(region_a.clone().zip(region_b.clone()));
+                histogram(
+                    a,
+                    region_a.end..a_range.end,
+                    b,
+                    region_b.end..b_range.end,
+                    matches,
+                );
+            }
+            None => fall_back(a, a_range.clone(), b, b_range.clone(), matches),
+        }
+    }
+    matches.extend((0..suffix).map(|k| (a_range.end + k, b_range.end + k)));
+}
+
+/// Find the longest region common to `a[a_range]` and `b[b_range]` among
+/// those whose rarest line occurs the fewest times in `a[a_range]`, as
+/// ranges of `a` and `b`. Returns `None` if every common line occurs more
+/// than [`Histogram::MAX_CHAIN`] times.
+fn common_region(
+    a: &[usize],
+    a_range: Range<usize>,
+    b: &[usize],
+    b_range: Range<usize>,
+) -> Option<(Range<usize>, Range<usize>)> {
+    let mut occurrences: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in a_range.clone() {
+        occurrences.entry(a[i]).or_default().push(i);
+    }
+    let count = |line| occurrences.get(&line).map_or(0, Vec::len);
+
+    // the best region so far, and the occurrences of its rarest line
+    let mut best: Option<(Range<usize>, Range<usize>, usize)> = None;
+    let mut j = b_range.start;
+    while j < b_range.end {
+        let mut next = j + 1;
+        let candidates = occurrences.get(&b[j]).map_or(&[][..], Vec::as_slice);
+        if candidates.len() <= Histogram::MAX_CHAIN {
+            for &i in candidates {
+                let (mut start_a, mut start_b) = (i, j);
+                while start_a > a_range.start
+                    && start_b > b_range.start
+                    && a[start_a - 1] == b[start_b - 1]
+                {
+                    start_a -= 1;
+                    start_b -= 1;
+                }
+                let (mut end_a, mut end_b) = (i + 1, j + 1);
+                while end_a < a_range.end && end_b < b_range.end && a[end_a] == b[end_b] {
+                    end_a += 1;
+                    end_b += 1;
+                }
+                next = next.max(end_b);
+
+                let rarest = a[start_a..end_a].iter().map(|&line| count(line)).min();
+                let rarest = rarest.expect("regions aren't empty");
+                let better = best.as_ref().is_none_or(|(best_a, _, best_rarest)| {
+                    rarest < *best_rarest
+                        || (rarest == *best_rarest && end_a - start_a > best_a.len())
+                });
+                if better {
+                    best = Some((start_a..end_a, start_b..end_b, rarest));
+                }
+            }
+        }
+        j = next;
+    }
+    best.map(|(region_a, region_b, _)| (region_a, region_b))
+}
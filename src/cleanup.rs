@@ -0,0 +1,493 @@
+//! Post-processing of edit scripts, for diffs that are easier to read.
+//!
+//! Where a block of inserted or deleted lines could equally well be placed
+//! a few lines up or down, because the lines around it repeat the lines at
+//! its edges, any placement is correct, but some read far better than
+//! others. Sliding moves such blocks to where their edges line up with
+//! blank lines and indentation, using git's indent heuristic, without
+//! making the diff any larger. Merging turns short runs of unchanged lines
+//! between changes into part of the changes, like diff-match-patch's
+//! semantic cleanup, so a rewritten paragraph isn't reported as many small
+//! changes around coincidentally equal lines, at the cost of reporting
+//! those lines as deleted and inserted.
+
+use crate::{Diff, Op};
+use std::ops::Range;
+
+/// The post-processing steps to apply to an edit script. Both are off by
+/// default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cleanup {
+    /// Slide each block of inserted or deleted lines that's surrounded by
+    /// unchanged lines to the best of the places it could be, judging by the
+    /// blank lines and indentation around its edges, like git's
+    /// `--indent-heuristic`. Blocks are only slid over lines that are the
+    /// same byte for byte.
+    pub slide: bool,
+
+    /// Merge runs of at most this many unchanged lines between two runs of
+    /// changes into the changes around them, when they're no longer than
+    /// the changes on either side.
+    pub merge_equalities: usize,
+}
+
+/// Post-process the edit script `diff` of the lines `old` against the lines
+/// `new`. Moves are left alone, and so are the blocks of changes next to
+/// them.
+pub fn clean_up<L: AsRef<[u8]>>(diff: &Diff, old: &[L], new: &[L], cleanup: &Cleanup) -> Diff {
+    let mut ops = diff.ops().to_vec();
+    if cleanup.slide {
+        slide(&mut ops, old, new);
+    }
+    if cleanup.merge_equalities > 0 {
+        merge_equalities(&mut ops, cleanup.merge_equalities);
+    }
+    Diff::from_ops(ops, diff.old_len(), diff.new_len())
+}
+
+/// The most lines git's indent heuristic considers sliding a block by.
+const MAX_SLIDING: usize = 100;
+
+/// Slide each block of inserted or deleted lines with only unchanged lines
+/// (or the start or end of the files) on either side.
+fn slide<L: AsRef<[u8]>>(ops: &mut Vec<Op>, old: &[L], new: &[L]) {
+    let mut x = 0;
+    while x < ops.len() {
+        let (lines, block) = match &ops[x] {
+            Op::Insert { new: block, .. } => (new, block.clone()),
+            Op::Delete { old: block, .. } => (old, block.clone()),
+            _ => {
+                x += 1;
+                continue;
+            }
+        };
+        let (block_start, block_end) = (start(&ops[x]), end(&ops[x]));
+        let neighbour = |x: Option<usize>| x.and_then(|x| ops.get(x));
+        let (Some(before), Some(after)) = (
+            equal_or_edge(neighbour(x.checked_sub(1))),
+            equal_or_edge(neighbour(Some(x + 1))),
+        ) else {
+            x += 1;
+            continue;
+        };
+
+        // how far the block can slide over the unchanged lines next to it;
+        // an unchanged run between two blocks isn't used up entirely, so
+        // the blocks stay apart
+        let before = match before {
+            Some(op) if end(op) == block_start => op.len() - usize::from(x >= 2),
+            _ => 0,
+        };
+        let after = match after {
+            Some(op) if start(op) == block_end => op.len() - usize::from(x + 2 < ops.len()),
+            _ => 0,
+        };
+        let same = |i: usize, j: usize| lines[i].as_ref() == lines[j].as_ref();
+        let up = (0..before)
+            .take_while(|&t| same(block.start - 1 - t, block.end - 1 - t))
+            .count();
+        let down = (0..after)
+            .take_while(|&t| same(block.start + t, block.end + t))
+            .count();
+        if up == 0 && down == 0 {
+            x += 1;
+            continue;
+        }
+
+        let best = best_end(lines, block.len(), block.end - up..block.end + down);
+        if best < block.end {
+            x += slide_up(ops, x, block.end - best);
+        } else if best > block.end {
+            x += slide_down(ops, x, best - block.end);
+        }
+        x += 1;
+    }
+    normalize(ops);
+}
+
+/// If `op` is unchanged lines, or there's no op at all, wrap it in `Some`.
+fn equal_or_edge(op: Option<&Op>) -> Option<Option<&Op>> {
+    match op {
+        None => Some(None),
+        Some(op @ Op::Equal { .. }) => Some(Some(op)),
+        Some(_) => None,
+    }
+}
+
+/// Where `op` starts, as lines of the old and new file.
+fn start(op: &Op) -> (usize, usize) {
+    (op.old_range().start, op.new_range().start)
+}
+
+/// Where `op` ends, as lines of the old and new file.
+fn end(op: &Op) -> (usize, usize) {
+    (op.old_range().end, op.new_range().end)
+}
+
+/// Slide the block `ops[x]` up by `t` lines, returning how many ops were
+/// inserted before it.
+fn slide_up(ops: &mut Vec<Op>, x: usize, t: usize) -> usize {
+    let (old_end, new_end) = end(&ops[x]);
+    if let Op::Equal { old, new } = &mut ops[x - 1] {
+        old.end -= t;
+        new.end -= t;
+    }
+    shift(&mut ops[x], |line| line - t);
+    match ops.get_mut(x + 1) {
+        Some(Op::Equal { old, new }) if (old.start, new.start) == (old_end, new_end) => {
+            old.start -= t;
+            new.start -= t;
+        }
+        _ => ops.insert(
+            x + 1,
+            Op::Equal {
+                old: old_end - t..old_end,
+                new: new_end - t..new_end,
+            },
+        ),
+    }
+    0
+}
+
+/// Slide the block `ops[x]` down by `t` lines, returning how many ops were
+/// inserted before it.
+fn slide_down(ops: &mut Vec<Op>, x: usize, t: usize) -> usize {
+    let (old_start, new_start) = start(&ops[x]);
+    if let Op::Equal { old, new } = &mut ops[x + 1] {
+        old.start += t;
+        new.start += t;
+    }
+    shift(&mut ops[x], |line| line + t);
+    match x.checked_sub(1).map(|x| &mut ops[x]) {
+        Some(Op::Equal { old, new }) if (old.end, new.end) == (old_start, new_start) => {
+            old.end += t;
+            new.end += t;
+            0
+        }
+        _ => {
+            ops.insert(
+                x,
+                Op::Equal {
+                    old: old_start..old_start + t,
+                    new: new_start..new_start + t,
+                },
+            );
+            1
+        }
+    }
+}
+
+/// Move every line number of `op` with `f`.
+fn shift(op: &mut Op, f: impl Fn(usize) -> usize) {
+    let range = |range: &mut Range<usize>| *range = f(range.start)..f(range.end);
+    match op {
+        Op::Insert { old, new } => {
+            *old = f(*old);
+            range(new);
+        }
+        Op::Delete { old, new } => {
+            range(old);
+            *new = f(*new);
+        }
+        Op::Equal { old, new } | Op::Move { old, new } => {
+            range(old);
+            range(new);
+        }
+    }
+}
+
+/// Choose where a block of `len` lines of `lines` that can end anywhere in
+/// `ends` should end, scoring the splits at its edges like git's indent
+/// heuristic. Ties go to the placement furthest down.
+fn best_end<L: AsRef<[u8]>>(lines: &[L], len: usize, ends: Range<usize>) -> usize {
+    let earliest = ends
+        .start
+        .max(ends.end.saturating_sub(len + 1))
+        .max(ends.end.saturating_sub(MAX_SLIDING));
+    let mut best: Option<(usize, Score)> = None;
+    for end in earliest..=ends.end {
+        let mut score = Score::default();
+        score.add(&Split::measure(lines, end));
+        score.add(&Split::measure(lines, end - len));
+        if best.as_ref().is_none_or(|(_, best)| score.cmp(best) <= 0) {
+            best = Some((end, score));
+        }
+    }
+    best.map_or(ends.end, |(end, _)| end)
+}
+
+const MAX_INDENT: usize = 200;
+const MAX_BLANKS: usize = 20;
+
+const START_OF_FILE_PENALTY: isize = 1;
+const END_OF_FILE_PENALTY: isize = 21;
+const TOTAL_BLANK_WEIGHT: isize = -30;
+const POST_BLANK_WEIGHT: isize = 6;
+const RELATIVE_INDENT_PENALTY: isize = -4;
+const RELATIVE_INDENT_WITH_BLANK_PENALTY: isize = 10;
+const RELATIVE_OUTDENT_PENALTY: isize = 24;
+const RELATIVE_OUTDENT_WITH_BLANK_PENALTY: isize = 17;
+const RELATIVE_DEDENT_PENALTY: isize = 23;
+const RELATIVE_DEDENT_WITH_BLANK_PENALTY: isize = 17;
+const INDENT_WEIGHT: isize = 60;
+
+/// The indentation of `line` in columns, with tab stops every 8 columns, or
+/// `None` if it's blank.
+fn indent(line: &[u8]) -> Option<usize> {
+    let mut indent = 0;
+    for &b in line {
+        match b {
+            b' ' => indent += 1,
+            b'\t' => indent += 8 - indent % 8,
+            b if b.is_ascii_whitespace() => {}
+            _ => return Some(indent),
+        }
+        if indent >= MAX_INDENT {
+            return Some(MAX_INDENT);
+        }
+    }
+    None
+}
+
+/// The surroundings of a split between two lines.
+struct Split {
+    /// Whether the split is at the end of the file.
+    end_of_file: bool,
+
+    /// The indentation of the line after the split, if it's not blank.
+    indent: Option<usize>,
+
+    /// The number of blank lines before the split.
+    pre_blank: usize,
+
+    /// The indentation of the first non-blank line before the split.
+    pre_indent: Option<usize>,
+
+    /// The number of blank lines after the line after the split.
+    post_blank: usize,
+
+    /// The indentation of the first non-blank line after those.
+    post_indent: Option<usize>,
+}
+
+impl Split {
+    /// Measure the split before line `split` of `lines`.
+    fn measure<L: AsRef<[u8]>>(lines: &[L], split: usize) -> Self {
+        let indent_of = |i: usize| indent(lines[i].as_ref());
+        let (pre_blank, pre_indent) = blanks((0..split).rev().map(indent_of));
+        let (post_blank, post_indent) = blanks((split + 1..lines.len()).map(indent_of));
+        Self {
+            end_of_file: split >= lines.len(),
+            indent: lines.get(split).and_then(|line| indent(line.as_ref())),
+            pre_blank,
+            pre_indent,
+            post_blank,
+            post_indent,
+        }
+    }
+}
+
+/// Count the blank lines at the start of `indents`, returning the count
+/// along with the indentation of the first non-blank line. After
+/// [`MAX_BLANKS`] blank lines, that's taken to be 0.
+fn blanks(indents: impl Iterator<Item = Option<usize>>) -> (usize, Option<usize>) {
+    let mut blank = 0;
+    for indent in indents {
+        if indent.is_some() {
+            return (blank, indent);
+        }
+        blank += 1;
+        if blank == MAX_BLANKS {
+            return (blank, Some(0));
+        }
+    }
+    (blank, None)
+}
+
+/// The badness of placing a block between a pair of splits; lower is
+/// better.
+#[derive(Debug, Default)]
+struct Score {
+    effective_indent: isize,
+    penalty: isize,
+}
+
+impl Score {
+    fn add(&mut self, split: &Split) {
+        if split.pre_indent.is_none() && split.pre_blank == 0 {
+            self.penalty += START_OF_FILE_PENALTY;
+        }
+        if split.end_of_file {
+            self.penalty += END_OF_FILE_PENALTY;
+        }
+
+        let post_blank = match split.indent {
+            None => 1 + split.post_blank,
+            Some(_) => 0,
+        };
+        let total_blank = split.pre_blank + post_blank;
+        self.penalty += TOTAL_BLANK_WEIGHT * total_blank as isize;
+        self.penalty += POST_BLANK_WEIGHT * post_blank as isize;
+
+        let indent = split.indent.or(split.post_indent);
+        let any_blanks = total_blank != 0;
+        self.effective_indent += indent.map_or(-1, |indent| indent as isize);
+        let (Some(indent), Some(pre_indent)) = (indent, split.pre_indent) else {
+            return;
+        };
+        self.penalty += if indent > pre_indent {
+            match any_blanks {
+                true => RELATIVE_INDENT_WITH_BLANK_PENALTY,
+                false => RELATIVE_INDENT_PENALTY,
+            }
+        } else if indent == pre_indent {
+            0
+        } else if split.post_indent.is_some_and(|post| post > indent) {
+            match any_blanks {
+                true => RELATIVE_OUTDENT_WITH_BLANK_PENALTY,
+                false => RELATIVE_OUTDENT_PENALTY,
+            }
+        } else {
+            match any_blanks {
+                true => RELATIVE_DEDENT_WITH_BLANK_PENALTY,
+                false => RELATIVE_DEDENT_PENALTY,
+            }
+        };
+    }
+
+    /// Compare two scores, negative if `self` is better.
+    fn cmp(&self, other: &Self) -> isize {
+        let indents = (self.effective_indent - other.effective_indent).signum();
+        INDENT_WEIGHT * indents + (self.penalty - other.penalty)
+    }
+}
+
+/// Merge each run of at most `max` unchanged lines between two runs of
+/// insertions and deletions into the runs around it, when it's no longer
+/// than the changes on either side.
+fn merge_equalities(ops: &mut Vec<Op>, max: usize) {
+    // merging grows the run of changes before the next equality, and the
+    // one after the previous, so pick up from the previous after each merge
+    let mut from = 1;
+    loop {
+        let mergeable = (from..ops.len().saturating_sub(1)).find(|&x| {
+            let len = ops[x].len();
+            matches!(ops[x], Op::Equal { .. })
+                && len <= max
+                && changes(ops[..x].iter().rev()).is_some_and(|size| len <= size)
+                && changes(ops[x + 1..].iter()).is_some_and(|size| len <= size)
+        });
+        let Some(x) = mergeable else {
+            break;
+        };
+
+        let Op::Equal { old, new } = ops[x].clone() else {
+            unreachable!()
+        };
+        let first = (0..x)
+            .rev()
+            .take_while(|&y| is_change(&ops[y]))
+            .last()
+            .expect("checked above");
+        let last = (x + 1..ops.len())
+            .take_while(|&y| is_change(&ops[y]))
+            .last()
+            .expect("checked above");
+        let mut run: Vec<Op> = ops[first..=last].to_vec();
+        run[x - first] = Op::Delete {
+            old: old.clone(),
+            new: new.start,
+        };
+        run.insert(
+            x - first + 1,
+            Op::Insert {
+                old: old.end,
+                new: new.clone(),
+            },
+        );
+        ops.splice(first..=last, canonical(&run));
+        from = first.max(2) - 1;
+    }
+}
+
+/// Whether `op` is an insertion or deletion.
+fn is_change(op: &Op) -> bool {
+    matches!(op, Op::Insert { .. } | Op::Delete { .. })
+}
+
+/// The size of the run of insertions and deletions `ops` starts with: the
+/// larger of the number of lines inserted and deleted. `None` if there's no
+/// such run, or it's next to a move.
+fn changes<'a>(ops: impl Iterator<Item = &'a Op>) -> Option<usize> {
+    let (mut deleted, mut inserted) = (0, 0);
+    for op in ops {
+        match op {
+            Op::Delete { old, .. } => deleted += old.len(),
+            Op::Insert { new, .. } => inserted += new.len(),
+            Op::Move { .. } => return None,
+            Op::Equal { .. } => break,
+        }
+    }
+    Some(deleted.max(inserted)).filter(|&size| size > 0)
+}
+
+/// Rewrite a run of insertions and deletions the way
+/// [`Diff`] lays them out: all deletions first, then all insertions,
+/// merging neighbouring ones.
+fn canonical(run: &[Op]) -> Vec<Op> {
+    let new_start = run[0].new_range().start;
+    let old_end = run[run.len() - 1].old_range().end;
+    let mut ops: Vec<Op> = Vec::new();
+    for op in run.iter().filter(|op| matches!(op, Op::Delete { .. })) {
+        match ops.last_mut() {
+            Some(Op::Delete { old, .. }) if old.end == op.old_range().start => {
+                old.end = op.old_range().end;
+            }
+            _ => ops.push(Op::Delete {
+                old: op.old_range(),
+                new: new_start,
+            }),
+        }
+    }
+    for op in run.iter().filter(|op| matches!(op, Op::Insert { .. })) {
+        match ops.last_mut() {
+            Some(Op::Insert { new, .. }) if new.end == op.new_range().start => {
+                new.end = op.new_range().end;
+            }
+            _ => ops.push(Op::Insert {
+                old: old_end,
+                new: op.new_range(),
+            }),
+        }
+    }
+    ops
+}
+
+/// Drop empty runs of unchanged lines, and merge neighbouring ops of the
+/// same kind that pick up where the other left off.
+fn normalize(ops: &mut Vec<Op>) {
+    ops.retain(|op| !matches!(op, Op::Equal { .. }) || !op.is_empty());
+    let mut merged: Vec<Op> = Vec::with_capacity(ops.len());
+    for op in ops.drain(..) {
+        match (merged.last_mut(), &op) {
+            (Some(Op::Equal { old, new }), Op::Equal { old: o, new: n })
+                if (old.end, new.end) == (o.start, n.start) =>
+            {
+                old.end = o.end;
+                new.end = n.end;
+            }
+            (Some(Op::Insert { old, new }), Op::Insert { old: o, new: n })
+                if *old == *o && new.end == n.start =>
+            {
+                new.end = n.end;
+            }
+            (Some(Op::Delete { old, new }), Op::Delete { old: o, new: n })
+                if *new == *n && old.end == o.start =>
+            {
+                old.end = o.end;
+            }
+            _ => merged.push(op),
+        }
+    }
+    *ops = merged;
+}
@@ -0,0 +1,136 @@
+//! Context (`diff -c`) output.
+
+use crate::output::{write_line, write_refined_line, LineRange, Moves, Spans, Style};
+use crate::{Hunk, LineEnding, Op, TextDiff};
+use std::fmt;
+use std::io::{self, Write};
+
+/// Context output takes the same options as unified output.
+pub use crate::unified::{Header, Options};
+
+/// Write `diff` to `out` in context format. Nothing is written if the files
+/// are identical.
+pub fn write<W: Write>(out: &mut W, diff: &TextDiff, options: &Options) -> io::Result<()> {
+    let hunks = diff.hunks(options.context);
+    if hunks.is_empty() {
+        return Ok(());
+    }
+
+    let palette = options.color.as_ref();
+    let moves = Moves::new(diff.diff());
+    let spans = Spans::new(diff, options.refine.filter(|_| palette.is_some()));
+    let header = |header| TraditionalHeader(header).to_string();
+    write_line(
+        out,
+        palette,
+        Style::Header,
+        b"*** ",
+        header(&options.old).as_bytes(),
+        LineEnding::Lf,
+    )?;
+    write_line(
+        out,
+        palette,
+        Style::Header,
+        b"--- ",
+        header(&options.new).as_bytes(),
+        LineEnding::Lf,
+    )?;
+    for hunk in hunks {
+        out.write_all(b"***************\n")?;
+        let ops = changes(&hunk);
+
+        let header = format!("*** {} ****", LineRange(hunk.old_range()));
+        write_line(
+            out,
+            palette,
+            Style::Hunk,
+            b"",
+            header.as_bytes(),
+            LineEnding::Lf,
+        )?;
+        if ops.iter().any(|(op, _)| matches!(op, Op::Delete { .. })) {
+            let text = diff.old_text();
+            for (op, changed) in &ops {
+                let prefix: &[u8] = match op {
+                    Op::Equal { .. } => b"  ",
+                    Op::Delete { .. } if *changed => b"! ",
+                    Op::Delete { .. } => b"- ",
+                    _ => continue,
+                };
+                for i in op.old_range() {
+                    let (style, spans) = match op {
+                        Op::Equal { .. } => (Style::Plain, &[][..]),
+                        _ => (Style::deleted(&moves, i), spans.of_old(i)),
+                    };
+                    let (line, ending) = (text.line(i), text.line_ending(i));
+                    write_refined_line(out, palette, style, prefix, line, spans, ending)?;
+                }
+            }
+        }
+
+        let header = format!("--- {} ----", LineRange(hunk.new_range()));
+        write_line(
+            out,
+            palette,
+            Style::Hunk,
+            b"",
+            header.as_bytes(),
+            LineEnding::Lf,
+        )?;
+        if ops.iter().any(|(op, _)| matches!(op, Op::Insert { .. })) {
+            let text = diff.new_text();
+            for (op, changed) in &ops {
+                let prefix: &[u8] = match op {
+                    Op::Equal { .. } => b"  ",
+                    Op::Insert { .. } if *changed => b"! ",
+                    Op::Insert { .. } => b"+ ",
+                    _ => continue,
+                };
+                for i in op.new_range() {
+                    let (style, spans) = match op {
+                        Op::Equal { .. } => (Style::Plain, &[][..]),
+                        _ => (Style::inserted(&moves, i), spans.of_new(i)),
+                    };
+                    let (line, ending) = (text.line(i), text.line_ending(i));
+                    write_refined_line(out, palette, style, prefix, line, spans, ending)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Render `diff` in context format. Lines are copied byte for byte, so the
+/// output is only valid UTF-8 if the files are.
+pub fn render(diff: &TextDiff, options: &Options) -> Vec<u8> {
+    let mut out = Vec::new();
+    write(&mut out, diff, options).expect("writing to a Vec never fails");
+    out
+}
+
+/// The operations in `hunk`, each paired with whether it's part of a change,
+/// i.e. a run of operations that both deletes and inserts lines. Context
+/// format marks those lines with `!` rather than `-` or `+`.
+fn changes(hunk: &Hunk) -> Vec<(&Op, bool)> {
+    let mut ops = Vec::with_capacity(hunk.ops().len());
+    for run in hunk
+        .ops()
+        .chunk_by(|a, b| matches!(a, Op::Equal { .. }) == matches!(b, Op::Equal { .. }))
+    {
+        let deletes = run.iter().any(|op| matches!(op, Op::Delete { .. }));
+        let inserts = run.iter().any(|op| matches!(op, Op::Insert { .. }));
+        ops.extend(run.iter().map(|op| (op, deletes && inserts)));
+    }
+    ops
+}
+
+/// A file header, with its timestamp in the traditional format context diffs
+/// use.
+struct TraditionalHeader<'a>(&'a Header);
+
+impl fmt::Display for TraditionalHeader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write(f, true)
+    }
+}
@@ -0,0 +1,479 @@
+use std::ops::Range;
+
+/// A single operation in an edit script. All line numbers are zero-based and
+/// all ranges are half-open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Op {
+    /// Lines `old` of the old file are unchanged, and appear as lines `new` of
+    /// the new file.
+    Equal {
+        old: Range<usize>,
+        new: Range<usize>,
+    },
+
+    /// Lines `new` of the new file were inserted before line `old` of the old
+    /// file.
+    Insert { old: usize, new: Range<usize> },
+
+    /// Lines `old` of the old file were deleted before line `new` of the new
+    /// file.
+    Delete { old: Range<usize>, new: usize },
+
+    /// Lines `old` of the old file were moved, unchanged, to lines `new` of
+    /// the new file. Both ranges have the same length.
+    Move {
+        old: Range<usize>,
+        new: Range<usize>,
+    },
+}
+
+impl Op {
+    /// The range of lines in the old file covered by this operation. This is
+    /// empty for insertions.
+    pub fn old_range(&self) -> Range<usize> {
+        match self {
+            Self::Equal { old, .. } | Self::Delete { old, .. } | Self::Move { old, .. } => {
+                old.clone()
+            }
+            Self::Insert { old, .. } => *old..*old,
+        }
+    }
+
+    /// The range of lines in the new file covered by this operation. This is
+    /// empty for deletions.
+    pub fn new_range(&self) -> Range<usize> {
+        match self {
+            Self::Equal { new, .. } | Self::Insert { new, .. } | Self::Move { new, .. } => {
+                new.clone()
+            }
+            Self::Delete { new, .. } => *new..*new,
+        }
+    }
+
+    /// The number of lines affected by this operation.
+    pub fn len(&self) -> usize {
+        self.old_range().len().max(self.new_range().len())
+    }
+
+    /// Whether this operation doesn't affect any lines.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The result of diffing two files: an edit script that, when applied in
+/// order, transforms the old file into the new file. Deserializing one fails
+/// unless its operations fit together that way.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "DiffParts")
+)]
+pub struct Diff {
+    ops: Vec<Op>,
+    old_len: usize,
+    new_len: usize,
+}
+
+/// A deserialized [`Diff`], before it's been checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct DiffParts {
+    ops: Vec<Op>,
+    old_len: usize,
+    new_len: usize,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<DiffParts> for Diff {
+    type Error = String;
+
+    fn try_from(parts: DiffParts) -> Result<Self, String> {
+        let diff = Self::from_ops(parts.ops, parts.old_len, parts.new_len);
+        diff.check()?;
+        Ok(diff)
+    }
+}
+
+impl Diff {
+    /// Build the edit script from the references left over after the fifth
+    /// pass. `new_refs[i]` is the (zero-based) line in the old file that line
+    /// `i` of the new file refers to, if any.
+    pub(crate) fn from_references(old_len: usize, new_refs: &[Option<usize>]) -> Self {
+        let blocks = blocks(new_refs);
+        let in_order = in_order(&blocks);
+
+        // blocks that aren't part of the in-order sequence have been moved;
+        // keep track of where they came from so the lines they cover aren't
+        // reported as deleted
+        let mut moved_from: Vec<&Block> = blocks
+            .iter()
+            .zip(&in_order)
+            .filter_map(|(block, keep)| (!keep).then_some(block))
+            .collect();
+        moved_from.sort_unstable_by_key(|block| block.old);
+        let mut moved_from = moved_from.into_iter().peekable();
+        let mut moved_to = blocks
+            .iter()
+            .zip(&in_order)
+            .filter_map(|(block, keep)| (!keep).then_some(block))
+            .peekable();
+
+        // walk both files in step, emitting the deletions, insertions and
+        // moves that fill the gaps between each pair of in-order blocks
+        let end = Block {
+            old: old_len,
+            new: new_refs.len(),
+            len: 0,
+        };
+        let mut ops = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        for block in blocks
+            .iter()
+            .zip(&in_order)
+            .filter_map(|(block, keep)| keep.then_some(block))
+            .chain([&end])
+        {
+            while let Some(moved) = moved_from.next_if(|moved| moved.old < block.old) {
+                if i < moved.old {
+                    ops.push(Op::Delete {
+                        old: i..moved.old,
+                        new: j,
+                    });
+                }
+                i = moved.old + moved.len;
+            }
+            if i < block.old {
+                ops.push(Op::Delete {
+                    old: i..block.old,
+                    new: j,
+                });
+            }
+
+            while let Some(moved) = moved_to.next_if(|moved| moved.new < block.new) {
+                if j < moved.new {
+                    ops.push(Op::Insert {
+                        old: block.old,
+                        new: j..moved.new,
+                    });
+                }
+                ops.push(Op::Move {
+                    old: moved.old..moved.old + moved.len,
+                    new: moved.new..moved.new + moved.len,
+                });
+                j = moved.new + moved.len;
+            }
+            if j < block.new {
+                ops.push(Op::Insert {
+                    old: block.old,
+                    new: j..block.new,
+                });
+            }
+
+            if block.len > 0 {
+                ops.push(Op::Equal {
+                    old: block.old..block.old + block.len,
+                    new: block.new..block.new + block.len,
+                });
+            }
+            i = block.old + block.len;
+            j = block.new + block.len;
+        }
+
+        Self {
+            ops,
+            old_len,
+            new_len: new_refs.len(),
+        }
+    }
+
+    /// An edit script made up of `ops`, which must walk both files in order
+    /// the way [`from_references`](Self::from_references) lays them out.
+    pub(crate) fn from_ops(ops: Vec<Op>, old_len: usize, new_len: usize) -> Self {
+        Self {
+            ops,
+            old_len,
+            new_len,
+        }
+    }
+
+    /// Check that the edit script is laid out the way
+    /// [`from_references`](Self::from_references) lays it out: the operations
+    /// cover each line of the new file once and in order, and equal and
+    /// deleted lines of the old file in order, with the lines moved elsewhere
+    /// making up the rest.
+    #[cfg(feature = "serde")]
+    fn check(&self) -> Result<(), String> {
+        let mut seen = vec![false; self.old_len];
+        let (mut i, mut j) = (0, 0);
+        for (k, op) in self.ops.iter().enumerate() {
+            let invalid = |problem: &str| Err(format!("operation {k} {problem}"));
+            let (old, new) = (op.old_range(), op.new_range());
+            if old.start > old.end || old.end > self.old_len {
+                return invalid("has an invalid range in the old file");
+            }
+            if new.start > new.end || new.end > self.new_len {
+                return invalid("has an invalid range in the new file");
+            }
+            if new.start != j {
+                return invalid("doesn't follow on from the last one in the new file");
+            }
+            match op {
+                Op::Equal { .. } | Op::Move { .. } if old.len() != new.len() => {
+                    return invalid("has ranges of different lengths");
+                }
+                Op::Equal { .. } | Op::Delete { .. } | Op::Insert { .. } if old.start < i => {
+                    return invalid("goes back in the old file");
+                }
+                _ => {}
+            }
+            if seen[old.clone()].contains(&true) {
+                return invalid("covers lines of the old file that were already covered");
+            }
+            seen[old.clone()].fill(true);
+            if !matches!(op, Op::Move { .. }) {
+                i = old.end;
+            }
+            j = new.end;
+        }
+
+        if j != self.new_len {
+            return Err(format!("line {} of the new file isn't covered", j + 1));
+        }
+        match seen.iter().position(|&seen| !seen) {
+            Some(i) => Err(format!("line {} of the old file isn't covered", i + 1)),
+            None => Ok(()),
+        }
+    }
+
+    /// The operations making up this edit script, in order.
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// The number of lines in the old file.
+    pub fn old_len(&self) -> usize {
+        self.old_len
+    }
+
+    /// The number of lines in the new file.
+    pub fn new_len(&self) -> usize {
+        self.new_len
+    }
+
+    /// The blocks of lines that were moved between the old and new file, in
+    /// the order they appear in the new file.
+    pub fn moves(&self) -> impl Iterator<Item = &Op> {
+        self.ops.iter().filter(|op| matches!(op, Op::Move { .. }))
+    }
+
+    /// Whether the old and new file are identical.
+    pub fn is_identical(&self) -> bool {
+        self.ops.iter().all(|op| matches!(op, Op::Equal { .. }))
+    }
+
+    /// The edit script with every move replaced by a deletion at its source
+    /// and an insertion at its destination, for output formats that can't
+    /// represent moves. The returned operations walk both files in order.
+    pub fn flatten(&self) -> Vec<Op> {
+        let end = Op::Equal {
+            old: self.old_len..self.old_len,
+            new: self.new_len..self.new_len,
+        };
+        let mut ops = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        for op in self
+            .ops
+            .iter()
+            .filter(|op| matches!(op, Op::Equal { .. }))
+            .chain([&end])
+        {
+            let (old, new) = (op.old_range(), op.new_range());
+            if i < old.start {
+                ops.push(Op::Delete {
+                    old: i..old.start,
+                    new: j,
+                });
+            }
+            if j < new.start {
+                ops.push(Op::Insert {
+                    old: old.start,
+                    new: j..new.start,
+                });
+            }
+            if !op.is_empty() {
+                ops.push(op.clone());
+            }
+            (i, j) = (old.end, new.end);
+        }
+        ops
+    }
+
+    /// Group the flattened edit script into hunks of changes, each surrounded
+    /// by up to `context` unchanged lines. Changes separated by no more than
+    /// twice that many unchanged lines share a hunk.
+    pub fn hunks(&self, context: usize) -> Vec<Hunk> {
+        let ops = self.flatten();
+        let mut hunks = Vec::new();
+        let mut hunk: Option<Hunk> = None;
+        for (idx, op) in ops.iter().enumerate() {
+            let Op::Equal { old, new } = op else {
+                let hunk = hunk.get_or_insert_with(|| {
+                    // lead with the tail of the preceding unchanged lines
+                    let mut hunk = Hunk::default();
+                    if let Some(Op::Equal { old, new }) = idx.checked_sub(1).map(|idx| &ops[idx]) {
+                        let n = context.min(old.len());
+                        hunk.push(Op::Equal {
+                            old: old.end - n..old.end,
+                            new: new.end - n..new.end,
+                        });
+                    }
+                    hunk
+                });
+                hunk.push(op.clone());
+                continue;
+            };
+
+            if let Some(mut current) = hunk.take() {
+                if old.len() <= 2 * context && idx + 1 < ops.len() {
+                    current.push(op.clone());
+                    hunk = Some(current);
+                } else {
+                    // trail with the head of these unchanged lines
+                    let n = context.min(old.len());
+                    current.push(Op::Equal {
+                        old: old.start..old.start + n,
+                        new: new.start..new.start + n,
+                    });
+                    hunks.push(current);
+                }
+            }
+        }
+        hunks.extend(hunk);
+        hunks
+    }
+}
+
+/// A group of nearby changes, along with the unchanged lines surrounding
+/// them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hunk {
+    old: Range<usize>,
+    new: Range<usize>,
+    ops: Vec<Op>,
+}
+
+impl Hunk {
+    fn push(&mut self, op: Op) {
+        if op.is_empty() {
+            return;
+        }
+        if self.ops.is_empty() {
+            self.old = op.old_range();
+            self.new = op.new_range();
+        }
+        self.old.end = op.old_range().end;
+        self.new.end = op.new_range().end;
+        self.ops.push(op);
+    }
+
+    /// The range of lines in the old file covered by this hunk.
+    pub fn old_range(&self) -> Range<usize> {
+        self.old.clone()
+    }
+
+    /// The range of lines in the new file covered by this hunk.
+    pub fn new_range(&self) -> Range<usize> {
+        self.new.clone()
+    }
+
+    /// The operations making up this hunk, in order. These never include
+    /// moves.
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+}
+
+/// A run of consecutive lines in the new file that refer to consecutive
+/// lines in the old file.
+#[derive(Debug)]
+struct Block {
+    old: usize,
+    new: usize,
+    len: usize,
+}
+
+/// Group references into blocks, ordered by their position in the new file.
+fn blocks(new_refs: &[Option<usize>]) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::new();
+    for (j, i) in new_refs.iter().enumerate() {
+        let Some(i) = *i else { continue };
+        if let Some(last) = blocks.last_mut() {
+            if last.old + last.len == i && last.new + last.len == j {
+                last.len += 1;
+                continue;
+            }
+        }
+        blocks.push(Block {
+            old: i,
+            new: j,
+            len: 1,
+        });
+    }
+    blocks
+}
+
+/// Pick the largest (by number of lines) set of blocks that appear in the
+/// same order in both files, i.e. a heaviest increasing subsequence of block
+/// positions in the old file. Returns whether each block was picked.
+fn in_order(blocks: &[Block]) -> Vec<bool> {
+    // rank each block by its position in the old file (1-based, so it can
+    // index the fenwick tree below)
+    let mut by_old: Vec<usize> = (0..blocks.len()).collect();
+    by_old.sort_unstable_by_key(|&b| blocks[b].old);
+    let mut rank = vec![0; blocks.len()];
+    for (r, &b) in by_old.iter().enumerate() {
+        rank[b] = r + 1;
+    }
+
+    // fenwick tree holding, for each prefix of ranks, the heaviest chain
+    // ending in that prefix and the block it ends with
+    let mut tree: Vec<(usize, Option<usize>)> = vec![(0, None); blocks.len() + 1];
+    let mut prev = vec![None; blocks.len()];
+    let mut best = (0, None);
+    for (b, block) in blocks.iter().enumerate() {
+        let mut chain = (0, None);
+        let mut k = rank[b] - 1;
+        while k > 0 {
+            if tree[k].0 > chain.0 {
+                chain = tree[k];
+            }
+            k &= k - 1;
+        }
+
+        let weight = chain.0 + block.len;
+        prev[b] = chain.1;
+        if weight > best.0 {
+            best = (weight, Some(b));
+        }
+
+        let mut k = rank[b];
+        while k < tree.len() {
+            if weight > tree[k].0 {
+                tree[k] = (weight, Some(b));
+            }
+            k += k & k.wrapping_neg();
+        }
+    }
+
+    let mut keep = vec![false; blocks.len()];
+    let mut b = best.1;
+    while let Some(i) = b {
+        keep[i] = true;
+        b = prev[i];
+    }
+    keep
+}
@@ -0,0 +1,150 @@
+//! ed script (`diff -e`) output, along with an interpreter for the subset of
+//! ed used by these scripts.
+//!
+//! Changes are written from the bottom of the file up, so applying one never
+//! shifts the line numbers of those after it.
+
+use crate::output::LineRange;
+use crate::{ApplyError, Op, Text, TextDiff};
+use std::io::{self, Write};
+use std::ops::Range;
+
+/// Write `diff` to `out` as an ed script. Nothing is written if the files
+/// are identical.
+///
+/// ed always ends lines with a newline, so this fails without writing
+/// anything if the files differ and either is missing the newline at the
+/// end, as the script couldn't reproduce the new file.
+pub fn write<W: Write>(out: &mut W, diff: &TextDiff) -> io::Result<()> {
+    let hunks = diff.hunks(0);
+    if hunks.is_empty() {
+        return Ok(());
+    }
+    for text in [diff.old_text(), diff.new_text()] {
+        if !text.is_empty() && text.is_missing_newline(text.len() - 1) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "ed scripts can't represent a missing newline at end of file",
+            ));
+        }
+    }
+
+    let text = diff.new_text();
+    for hunk in hunks.iter().rev() {
+        let (old, new) = (hunk.old_range(), hunk.new_range());
+        match (old.is_empty(), new.is_empty()) {
+            (true, _) => writeln!(out, "{}a", old.start)?,
+            (_, true) => writeln!(out, "{}d", LineRange(old))?,
+            _ => writeln!(out, "{}c", LineRange(old))?,
+        }
+        if new.is_empty() {
+            continue;
+        }
+
+        let mut inserting = true;
+        for op in hunk.ops() {
+            let Op::Insert { new, .. } = op else {
+                continue;
+            };
+            for i in new.clone() {
+                if !inserting {
+                    out.write_all(b"a\n")?;
+                    inserting = true;
+                }
+                if text.raw_line(i) == b".\n" {
+                    // a lone dot would end the insertion, so insert a
+                    // doubled one instead and strip it afterwards
+                    out.write_all(b"..\n.\ns/.//\n")?;
+                    inserting = false;
+                } else {
+                    out.write_all(text.raw_line(i))?;
+                }
+            }
+        }
+        if inserting {
+            out.write_all(b".\n")?;
+        }
+    }
+    Ok(())
+}
+
+/// Render `diff` as an ed script. Lines are copied byte for byte, so the
+/// output is only valid UTF-8 if the files are. Fails if the script can't
+/// reproduce the new file, see [`write`].
+pub fn render(diff: &TextDiff) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    write(&mut out, diff)?;
+    Ok(out)
+}
+
+/// Run the ed script `script` on `old`, returning the edited file. Only the
+/// commands [`write`] produces are supported: `a`, `c` and `d`, with
+/// optional line addresses, and `s/.//`.
+pub fn apply(old: &[u8], script: &[u8]) -> Result<Vec<u8>, ApplyError> {
+    let old = Text::new(old);
+    let script = Text::new(script);
+    let mut lines: Vec<&[u8]> = old.raw_lines().collect();
+
+    // the number of lines up to and including the current line
+    let mut current = lines.len();
+    let mut i = 0;
+    while i < script.len() {
+        let n = i;
+        let invalid = || ApplyError::InvalidScript { line: n };
+        let command = script.line(i);
+        i += 1;
+
+        if command == b"s/.//" {
+            match current.checked_sub(1).and_then(|n| lines.get_mut(n)) {
+                Some(line) if line.starts_with(b".") => *line = &line[1..],
+                _ => return Err(invalid()),
+            }
+            continue;
+        }
+
+        let (&name, address) = command.split_last().ok_or_else(invalid)?;
+        let range = match address {
+            b"" => current..current,
+            address => parse_range(address, lines.len()).ok_or_else(invalid)?,
+        };
+        let at = match name {
+            b'a' => range.end,
+            b'c' | b'd' if !range.is_empty() => {
+                lines.drain(range.clone());
+                range.start
+            }
+            _ => return Err(invalid()),
+        };
+
+        if name == b'd' {
+            // the line after the deleted ones becomes the current line
+            current = (at + 1).min(lines.len());
+            continue;
+        }
+        let start = i;
+        while i < script.len() && script.raw_line(i) != b".\n" {
+            i += 1;
+        }
+        if i == script.len() {
+            return Err(invalid());
+        }
+        lines.splice(at..at, (start..i).map(|i| script.raw_line(i)));
+        current = at + (i - start);
+        i += 1;
+    }
+    Ok(lines.concat())
+}
+
+/// Parse an address like `3` or `5,7` into the (zero-based, half-open) range
+/// of lines it refers to, as long as it lies within a file of `len` lines.
+/// The address 0 gives an empty range, for appending at the start of the
+/// file.
+fn parse_range(address: &[u8], len: usize) -> Option<Range<usize>> {
+    let number = |s: &[u8]| std::str::from_utf8(s).ok()?.parse::<usize>().ok();
+    let (first, last) = match address.iter().position(|&b| b == b',') {
+        Some(comma) => (number(&address[..comma])?, number(&address[comma + 1..])?),
+        None if address == b"0" => return Some(0..0),
+        None => (number(address)?, number(address)?),
+    };
+    (1 <= first && first <= last && last <= len).then_some(first - 1..last)
+}
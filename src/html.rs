@@ -0,0 +1,391 @@
+//! Self-contained HTML reports, with both a side-by-side and an inline view
+//! of the changes.
+//!
+//! The report is a single file with its styles inline and no scripts, so it
+//! can be attached to a CI run or sent around as is. Each hunk can be linked
+//! to, changed lines are paired up with the lines replacing them and the
+//! parts that differ highlighted, and moved blocks are shown in the same
+//! color where they were moved from and where they were moved to.
+
+use crate::output::{pair_changes, Moves};
+use crate::refine::{refine, Granularity, Refinement};
+use crate::unified::{Header, HunkRange};
+use crate::{Hunk, Op, Text, TextDiff};
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::ops::Range;
+
+/// The number of colors moved blocks cycle through.
+const MOVE_COLORS: usize = 6;
+
+const STYLE: &str = "\
+body { margin: 0; font-family: system-ui, sans-serif; color: #1f2328; background: #fff; }
+header { padding: 12px 16px; border-bottom: 1px solid #d0d7de; }
+header h1 { margin: 0 0 8px; font-size: 18px; }
+header label { margin-right: 12px; cursor: pointer; }
+nav { padding: 8px 16px; font-size: 13px; }
+nav a { margin-right: 12px; }
+#view-side:checked ~ header [for=view-side], #view-inline:checked ~ header [for=view-inline] { font-weight: bold; }
+#view-side:checked ~ main .inline, #view-inline:checked ~ main .side { display: none; }
+table { width: 100%; border-collapse: collapse; table-layout: fixed; font: 12px/1.5 ui-monospace, monospace; }
+td { padding: 0 8px; vertical-align: top; white-space: pre-wrap; word-break: break-all; tab-size: 8; }
+td.num { width: 48px; color: #6e7781; text-align: right; user-select: none; }
+td.sign { width: 12px; user-select: none; }
+tr.hunk td { padding: 4px 8px; color: #57606a; background: #ddf4ff; }
+tr.hunk a { color: inherit; text-decoration: none; }
+.delete { background: #ffebe9; }
+.insert { background: #e6ffec; }
+.delete .hl { background: #ffb3ad; }
+.insert .hl { background: #9ff0b0; }
+.empty { background: #f6f8fa; }
+.note { color: #6e7781; font-style: italic; }
+.move-0 { background: #fff1c2; }
+.move-1 { background: #e4d8fd; }
+.move-2 { background: #c9f0f5; }
+.move-3 { background: #ffd8b5; }
+.move-4 { background: #ffd3ec; }
+.move-5 { background: #d9e8b4; }
+";
+
+/// Options for HTML output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// The number of unchanged lines to show around each change.
+    pub context: usize,
+
+    /// The header for the old file.
+    pub old: Header,
+
+    /// The header for the new file.
+    pub new: Header,
+
+    /// How finely to split changed lines to highlight the parts that
+    /// changed, or `None` not to highlight them.
+    pub refine: Option<Granularity>,
+}
+
+impl Options {
+    pub fn new(old: Header, new: Header) -> Self {
+        Self {
+            context: 3,
+            old,
+            new,
+            refine: Some(Granularity::Word),
+        }
+    }
+}
+
+/// Write `diff` to `out` as an HTML document. Unlike the text formats, this
+/// always writes a document, even if the files are identical.
+pub fn write<W: Write>(out: &mut W, diff: &TextDiff, options: &Options) -> io::Result<()> {
+    let hunks = diff.hunks(options.context);
+    let moves = Moves::new(diff.diff());
+    let (old_label, new_label) = (options.old.to_string(), options.new.to_string());
+    let title = format!("{} → {}", options.old.label, options.new.label);
+
+    write!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>\n{STYLE}</style>\n</head>\n<body>\n",
+        escape(&title)
+    )?;
+    writeln!(
+        out,
+        "<input type=\"radio\" name=\"view\" id=\"view-side\" checked hidden>\n\
+         <input type=\"radio\" name=\"view\" id=\"view-inline\" hidden>"
+    )?;
+    writeln!(
+        out,
+        "<header>\n<h1>{}</h1>\n<div>--- {}<br>+++ {}</div>\n<p>\
+         <label for=\"view-side\">Side by side</label>\
+         <label for=\"view-inline\">Inline</label></p>\n</header>",
+        escape(&title),
+        escape(&old_label),
+        escape(&new_label)
+    )?;
+
+    writeln!(out, "<main>")?;
+    if hunks.is_empty() {
+        writeln!(out, "<p class=\"note\">The files are identical.</p>")?;
+    }
+    let view = View {
+        diff,
+        refine: options.refine,
+        moves: &moves,
+        hunks: &hunks,
+    };
+    view.side_by_side(out)?;
+    view.inline(out)?;
+    writeln!(out, "</main>\n</body>\n</html>")
+}
+
+/// Render `diff` as an HTML document.
+pub fn render(diff: &TextDiff, options: &Options) -> String {
+    let mut out = Vec::new();
+    write(&mut out, diff, options).expect("writing to a Vec never fails");
+    String::from_utf8(out).expect("the document is always valid UTF-8")
+}
+
+/// Everything needed to render the views of the changes.
+struct View<'a> {
+    diff: &'a TextDiff<'a>,
+    refine: Option<Granularity>,
+    moves: &'a Moves,
+    hunks: &'a [Hunk],
+}
+
+impl View<'_> {
+    /// Write the side-by-side view, with the old file on the left and the
+    /// new file on the right.
+    fn side_by_side<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "<section class=\"side\">")?;
+        self.nav(out, "side")?;
+        writeln!(out, "<table>")?;
+        for (n, hunk) in self.hunks.iter().enumerate() {
+            self.hunk_header(out, "side", n, hunk)?;
+            for group in changes(hunk) {
+                if let [Op::Equal { old, new }] = group {
+                    for (i, j) in old.clone().zip(new.clone()) {
+                        write!(out, "<tr>")?;
+                        self.cell(out, true, i, None)?;
+                        self.cell(out, false, j, None)?;
+                        writeln!(out, "</tr>")?;
+                    }
+                    continue;
+                }
+
+                for (i, j) in pair_changes(group, self.moves) {
+                    let highlights = self.highlights(i, j);
+                    write!(out, "<tr>")?;
+                    match i {
+                        Some(i) => self.cell(out, true, i, Some(&highlights.old))?,
+                        None => write!(
+                            out,
+                            "<td class=\"num empty\"></td><td class=\"empty\"></td>"
+                        )?,
+                    }
+                    match j {
+                        Some(j) => self.cell(out, false, j, Some(&highlights.new))?,
+                        None => write!(
+                            out,
+                            "<td class=\"num empty\"></td><td class=\"empty\"></td>"
+                        )?,
+                    }
+                    writeln!(out, "</tr>")?;
+                }
+            }
+        }
+        writeln!(out, "</table>\n</section>")
+    }
+
+    /// Write the inline view, with deleted lines followed by the lines
+    /// inserted in their place, like a unified diff.
+    fn inline<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let (old, new) = (self.diff.old_text(), self.diff.new_text());
+        writeln!(out, "<section class=\"inline\">")?;
+        self.nav(out, "inline")?;
+        writeln!(out, "<table>")?;
+        for (n, hunk) in self.hunks.iter().enumerate() {
+            self.hunk_header(out, "inline", n, hunk)?;
+            for group in changes(hunk) {
+                if let [Op::Equal {
+                    old: lines,
+                    new: new_lines,
+                }] = group
+                {
+                    for (i, j) in lines.clone().zip(new_lines.clone()) {
+                        write!(
+                            out,
+                            "<tr><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+                             <td class=\"sign\"> </td><td class=\"equal\">",
+                            i + 1,
+                            j + 1
+                        )?;
+                        write_line(out, new, j, &[])?;
+                        writeln!(out, "</td></tr>")?;
+                    }
+                    continue;
+                }
+
+                let pairs = pair_changes(group, self.moves);
+                let highlights: Vec<_> =
+                    pairs.iter().map(|&(i, j)| self.highlights(i, j)).collect();
+                for (&(i, _), highlights) in pairs.iter().zip(&highlights) {
+                    let Some(i) = i else { continue };
+                    let (class, title) = self.class(true, i, "delete");
+                    write!(
+                        out,
+                        "<tr><td class=\"num\">{}</td><td class=\"num\"></td>\
+                         <td class=\"sign {class}\">-</td><td class=\"{class}\"{title}>",
+                        i + 1
+                    )?;
+                    write_line(out, old, i, &highlights.old)?;
+                    writeln!(out, "</td></tr>")?;
+                }
+                for (&(_, j), highlights) in pairs.iter().zip(&highlights) {
+                    let Some(j) = j else { continue };
+                    let (class, title) = self.class(false, j, "insert");
+                    write!(
+                        out,
+                        "<tr><td class=\"num\"></td><td class=\"num\">{}</td>\
+                         <td class=\"sign {class}\">+</td><td class=\"{class}\"{title}>",
+                        j + 1
+                    )?;
+                    write_line(out, new, j, &highlights.new)?;
+                    writeln!(out, "</td></tr>")?;
+                }
+            }
+        }
+        writeln!(out, "</table>\n</section>")
+    }
+
+    /// Write links to each hunk of a view.
+    fn nav<W: Write>(&self, out: &mut W, view: &str) -> io::Result<()> {
+        if self.hunks.is_empty() {
+            return Ok(());
+        }
+        write!(out, "<nav>")?;
+        for (n, hunk) in self.hunks.iter().enumerate() {
+            write!(
+                out,
+                "<a href=\"#{view}-hunk-{}\">-{} +{}</a>",
+                n + 1,
+                HunkRange(hunk.old_range()),
+                HunkRange(hunk.new_range())
+            )?;
+        }
+        writeln!(out, "</nav>")
+    }
+
+    /// Write the header row of hunk `n`, which is also its anchor.
+    fn hunk_header<W: Write>(
+        &self,
+        out: &mut W,
+        view: &str,
+        n: usize,
+        hunk: &Hunk,
+    ) -> io::Result<()> {
+        let id = format!("{view}-hunk-{}", n + 1);
+        writeln!(
+            out,
+            "<tr class=\"hunk\" id=\"{id}\"><td colspan=\"4\">\
+             <a href=\"#{id}\">@@ -{} +{} @@</a></td></tr>",
+            HunkRange(hunk.old_range()),
+            HunkRange(hunk.new_range())
+        )
+    }
+
+    /// Write the line number and text of line `i` of the old file, or of
+    /// the new file if `old` isn't set. Changed lines are given with their
+    /// highlights, and unchanged lines without.
+    fn cell<W: Write>(
+        &self,
+        out: &mut W,
+        old: bool,
+        i: usize,
+        highlights: Option<&[Range<usize>]>,
+    ) -> io::Result<()> {
+        let (text, class) = match old {
+            true => (self.diff.old_text(), "delete"),
+            false => (self.diff.new_text(), "insert"),
+        };
+        let (class, title) = match highlights {
+            Some(_) => self.class(old, i, class),
+            None => (Cow::Borrowed("equal"), String::new()),
+        };
+        write!(
+            out,
+            "<td class=\"num\">{}</td><td class=\"{class}\"{title}>",
+            i + 1
+        )?;
+        write_line(out, text, i, highlights.unwrap_or_default())?;
+        write!(out, "</td>")
+    }
+
+    /// The class and title attribute for changed line `i` of the old file,
+    /// or of the new file if `old` isn't set. Moved lines are colored by the
+    /// block they belong to, so they match where they were moved from and
+    /// to.
+    fn class(&self, old: bool, i: usize, class: &'static str) -> (Cow<'static, str>, String) {
+        let moved = match old {
+            true => self.moves.of_old(i),
+            false => self.moves.of_new(i),
+        };
+        let Some(k) = moved else {
+            return (Cow::Borrowed(class), String::new());
+        };
+        let (from, to) = self.moves.start(k);
+        let title = match old {
+            true => format!(" title=\"Moved to line {}\"", to + i - from + 1),
+            false => format!(" title=\"Moved from line {}\"", from + i - to + 1),
+        };
+        (Cow::Owned(format!("move move-{}", k % MOVE_COLORS)), title)
+    }
+
+    /// The parts of a pair of changed lines that differ, if both are given.
+    fn highlights(&self, old: Option<usize>, new: Option<usize>) -> Refinement {
+        let (Some(i), Some(j), Some(granularity)) = (old, new, self.refine) else {
+            return Refinement::default();
+        };
+        // refine the lines as they're shown, so the spans fall on character
+        // boundaries even if the files aren't valid UTF-8
+        let old = self.diff.old_text().line_lossy(i);
+        let new = self.diff.new_text().line_lossy(j);
+        refine(old.as_bytes(), new.as_bytes(), &granularity)
+    }
+}
+
+/// Split the operations of `hunk` into runs of unchanged lines and runs of
+/// changes.
+fn changes(hunk: &Hunk) -> impl Iterator<Item = &[Op]> {
+    hunk.ops()
+        .chunk_by(|a, b| !matches!(a, Op::Equal { .. }) && !matches!(b, Op::Equal { .. }))
+}
+
+/// Write line `i` of `text`, with the byte ranges `highlights` of its
+/// contents highlighted.
+fn write_line<W: Write>(
+    out: &mut W,
+    text: &Text,
+    i: usize,
+    highlights: &[Range<usize>],
+) -> io::Result<()> {
+    let line = text.line_lossy(i);
+    let mut end = 0;
+    for range in highlights {
+        write!(
+            out,
+            "{}<span class=\"hl\">{}</span>",
+            escape(&line[end..range.start]),
+            escape(&line[range.clone()])
+        )?;
+        end = range.end;
+    }
+    write!(out, "{}", escape(&line[end..]))?;
+    if text.is_missing_newline(i) {
+        write!(
+            out,
+            "<span class=\"note\"> (no newline at end of file)</span>"
+        )?;
+    }
+    Ok(())
+}
+
+/// Escape `text` for use in HTML text and attribute values.
+fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 16);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
@@ -0,0 +1,176 @@
+//! JSON output, for consuming diffs as data.
+//!
+//! The output is a single JSON object, following the schema in
+//! `schema/diff.v1.json`. Diffing the lines `a b c` against
+//! `c a d` gives:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "old": { "label": "old.txt", "lines": 3, "missing_newline": false },
+//!   "new": { "label": "new.txt", "lines": 3, "missing_newline": false },
+//!   "identical": false,
+//!   "operations": [
+//!     { "kind": "delete", "old": [1, 2], "new": [0, 0], "lines": ["b\n"] },
+//!     { "kind": "equal", "old": [2, 3], "new": [0, 1], "lines": ["c\n"] },
+//!     { "kind": "move", "old": [0, 1], "new": [1, 2], "lines": ["a\n"] },
+//!     { "kind": "insert", "old": [3, 3], "new": [2, 3], "lines": ["d\n"] }
+//!   ],
+//!   "moves": [{ "old": [0, 1], "new": [1, 2] }]
+//! }
+//! ```
+//!
+//! - `version` is the version of the schema, [`VERSION`]. It's only bumped
+//!   for changes that could break existing consumers.
+//! - `lines` in `old` and `new` is the number of lines in each file, and
+//!   `missing_newline` whether its last line is missing its newline.
+//! - `identical` is whether the files are identical, apart from any changes
+//!   that were ignored.
+//! - `operations` is the edit script, in the order of the new file. Ranges of
+//!   lines are zero-based `[start, end]` pairs, with `end` excluded. Each
+//!   operation carries the lines it covers, from the old file for deletions
+//!   and moves and from the new file otherwise. Lines include their
+//!   terminator (`\n` or `\r\n`, or none for a last line missing its
+//!   newline), and invalid UTF-8 is replaced with `U+FFFD REPLACEMENT
+//!   CHARACTER`.
+//! - `moves` pairs up the source and destination of each moved block. The
+//!   lines of a moved block aren't reported again where they were moved
+//!   from.
+
+use crate::{Op, Text, TextDiff};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::ops::Range;
+
+/// The version of the JSON schema written by [`write`].
+pub const VERSION: u32 = 1;
+
+/// Options for JSON output.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options {
+    /// The label for the old file, usually its path.
+    pub old: String,
+
+    /// The label for the new file, usually its path.
+    pub new: String,
+}
+
+impl Options {
+    pub fn new(old: impl Into<String>, new: impl Into<String>) -> Self {
+        Self {
+            old: old.into(),
+            new: new.into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u32,
+    old: File<'a>,
+    new: File<'a>,
+    identical: bool,
+    operations: Vec<Operation<'a>>,
+    moves: Vec<Move>,
+}
+
+#[derive(Serialize)]
+struct File<'a> {
+    label: &'a str,
+    lines: usize,
+    missing_newline: bool,
+}
+
+impl<'a> File<'a> {
+    fn new(label: &'a str, text: &Text) -> Self {
+        Self {
+            label,
+            lines: text.len(),
+            missing_newline: !text.is_empty() && text.is_missing_newline(text.len() - 1),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Equal,
+    Insert,
+    Delete,
+    Move,
+}
+
+#[derive(Serialize)]
+struct Operation<'a> {
+    kind: Kind,
+    old: [usize; 2],
+    new: [usize; 2],
+    lines: Vec<Cow<'a, str>>,
+}
+
+#[derive(Serialize)]
+struct Move {
+    old: [usize; 2],
+    new: [usize; 2],
+}
+
+/// Write `diff` to `out` as a single line of JSON. Unlike the text formats,
+/// this always writes a document, even if the files are identical.
+pub fn write<W: Write>(out: &mut W, diff: &TextDiff, options: &Options) -> io::Result<()> {
+    let (old, new) = (diff.old_text(), diff.new_text());
+    let pair = |range: Range<usize>| [range.start, range.end];
+
+    let operations = diff
+        .diff()
+        .ops()
+        .iter()
+        .map(|op| {
+            let (kind, lines) = match op {
+                Op::Equal { new: range, .. } => (Kind::Equal, lines(new, range.clone())),
+                Op::Insert { new: range, .. } => (Kind::Insert, lines(new, range.clone())),
+                Op::Delete { old: range, .. } => (Kind::Delete, lines(old, range.clone())),
+                Op::Move { old: range, .. } => (Kind::Move, lines(old, range.clone())),
+            };
+            Operation {
+                kind,
+                old: pair(op.old_range()),
+                new: pair(op.new_range()),
+                lines,
+            }
+        })
+        .collect();
+    let moves = diff
+        .diff()
+        .moves()
+        .map(|op| Move {
+            old: pair(op.old_range()),
+            new: pair(op.new_range()),
+        })
+        .collect();
+
+    let document = Document {
+        version: VERSION,
+        old: File::new(&options.old, old),
+        new: File::new(&options.new, new),
+        identical: diff.is_identical(),
+        operations,
+        moves,
+    };
+    serde_json::to_writer(&mut *out, &document)?;
+    out.write_all(b"\n")
+}
+
+/// Lines `range` of `text`, including their terminators.
+fn lines<'a>(text: &'a Text, range: Range<usize>) -> Vec<Cow<'a, str>> {
+    range
+        .map(|i| String::from_utf8_lossy(text.raw_line(i)))
+        .collect()
+}
+
+/// Render `diff` as JSON.
+pub fn render(diff: &TextDiff, options: &Options) -> String {
+    let mut out = Vec::new();
+    write(&mut out, diff, options).expect("writing to a Vec never fails");
+    String::from_utf8(out).expect("JSON is always valid UTF-8")
+}
@@ -1,9 +1,37 @@
 #![allow(non_snake_case)]
-use std::cell::RefCell;
+use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
-use std::hash::{DefaultHasher, Hasher};
-use std::io::{BufRead, BufReader, Read};
-use std::rc::Rc;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::io::Read;
+use std::ops::Range;
+
+pub mod algorithm;
+pub mod cleanup;
+pub mod context;
+mod diff;
+pub mod ed;
+pub mod html;
+#[cfg(feature = "serde")]
+pub mod json;
+mod myers;
+pub mod normal;
+mod options;
+mod output;
+mod palette;
+mod patch;
+pub mod rcs;
+pub mod refine;
+pub mod side_by_side;
+mod text;
+pub mod tokenize;
+pub mod unified;
+
+pub use diff::{Diff, Hunk, Op};
+pub use options::{DiffOptions, Normalizer};
+pub use palette::{Palette, ParsePaletteError};
+pub use patch::{apply, ApplyError, Patch, PatchOp};
+pub use text::{LineEnding, Text, TextDiff};
 
 /// The number of times a line occurs in the old or new file. We only care
 /// whether it:
@@ -11,8 +39,9 @@ use std::rc::Rc;
 /// - doesn't occur
 /// - occurs exactly once
 /// - occurs more than once
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq)]
 enum Occurrences {
+    #[default]
     Zero,
     One,
     Many,
@@ -28,7 +57,7 @@ impl Occurrences {
 }
 
 /// An entry in the symbol table.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct SymbolEntry {
     /// The number of occurrences in the old file.
     OC: Occurrences,
@@ -37,47 +66,151 @@ struct SymbolEntry {
     NC: Occurrences,
 
     /// The line number of the distinct occurrence in the old file (only
-    /// set when the entry occurs exactly once in the old file).
-    OLNO: Option<usize>,
-
-    /// The line contents.
-    line: String,
+    /// meaningful when the entry occurs exactly once in the old file).
+    OLNO: u32,
 }
 
 /// A symbol from either the old file (OA) or new file (NA). This will either
 /// point to an entry in the symbol table, or the corresponding line in the
-/// other file.
-#[derive(Debug)]
-enum Symbol {
-    /// Reference to an entry in the symbol table.
-    Entry(Rc<RefCell<SymbolEntry>>),
+/// other file. Both are packed into a single `u32`, with the high bit set
+/// for references, to keep OA and NA compact.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Symbol(u32);
+
+impl Symbol {
+    const REFERENCE: u32 = 1 << 31;
+
+    /// Reference to entry `index` in the symbol table.
+    fn entry(index: usize) -> Self {
+        assert!(index < Self::REFERENCE as usize, "too many distinct lines");
+        Self(index as u32)
+    }
 
     /// Reference to the corresponding line in the other file.
-    Reference(usize),
+    fn reference(line_num: usize) -> Self {
+        assert!(line_num < Self::REFERENCE as usize, "too many lines");
+        Self(line_num as u32 | Self::REFERENCE)
+    }
+
+    fn as_entry(self) -> Option<usize> {
+        (self.0 & Self::REFERENCE == 0).then_some(self.0 as usize)
+    }
+
+    fn as_reference(self) -> Option<usize> {
+        (self.0 & Self::REFERENCE != 0).then_some((self.0 & !Self::REFERENCE) as usize)
+    }
 }
 
-impl Symbol {
-    fn as_entry_mut(&mut self) -> &mut Rc<RefCell<SymbolEntry>> {
-        match self {
-            Self::Entry(ref mut entry) => entry,
-            _ => panic!("tried to access non-entry as entry"),
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.as_entry(), self.as_reference()) {
+            (Some(index), _) => f.debug_tuple("Entry").field(&index).finish(),
+            (_, Some(line_num)) => f.debug_tuple("Reference").field(&line_num).finish(),
+            _ => unreachable!(),
         }
     }
 }
 
-pub fn heckel_diff<O: Read, N: Read>(O: O, N: N) -> eyre::Result<()> {
-    let O = BufReader::new(O);
-    let N = BufReader::new(N);
+/// Diff the lines of the old file `O` against the new file `N`, returning an
+/// edit script that transforms one into the other, along with the lines it
+/// refers to. The files don't need to be valid UTF-8.
+pub fn heckel_diff<O: Read, N: Read>(mut O: O, mut N: N) -> eyre::Result<TextDiff<'static>> {
+    let mut old = Vec::new();
+    O.read_to_end(&mut old)?;
+    let mut new = Vec::new();
+    N.read_to_end(&mut new)?;
+
+    Ok(TextDiff::new(Text::new(old), Text::new(new)))
+}
+
+/// Diff the lines of `old` against the lines of `new`. The result borrows
+/// both strings, so the only allocations are the line and symbol tables.
+pub fn diff_str<'a>(old: &'a str, new: &'a str) -> TextDiff<'a> {
+    diff_bytes(old.as_bytes(), new.as_bytes())
+}
+
+/// Like `diff_str`, but for text that may not be valid UTF-8.
+pub fn diff_bytes<'a>(old: &'a [u8], new: &'a [u8]) -> TextDiff<'a> {
+    TextDiff::new(Text::new(old), Text::new(new))
+}
+
+/// Diff the old sequence `O` against the new sequence `N`, returning an edit
+/// script that transforms one into the other. Each element is treated the
+/// same way `heckel_diff` treats a line, so this can be used to diff tokens,
+/// records, or any other kind of symbol.
+pub fn diff_slices<T: Hash + Eq>(O: &[T], N: &[T]) -> Diff {
+    diff_slices_with_hasher(O, N, RandomState::new())
+}
+
+/// Like `diff_slices`, but uses `hasher` to hash symbols in the symbol table.
+/// Symbols are always compared by value, so a weak or even constant hasher
+/// only affects performance, never the diff itself.
+pub fn diff_slices_with_hasher<T: Hash + Eq, S: BuildHasher>(O: &[T], N: &[T], hasher: S) -> Diff {
+    Heckel::default().diff_with_hasher(O, N, hasher)
+}
+
+/// Options for Heckel's algorithm itself, as opposed to how lines are
+/// compared (see [`DiffOptions`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Heckel {
+    /// Match up the lines in each gap between matched lines with a longest
+    /// common subsequence, found with Myers' algorithm.
+    ///
+    /// Heckel's algorithm only anchors on lines that occur exactly once in
+    /// each file, so a region between anchors that's made up of repeated
+    /// lines (`}`, blank lines, `end`) is reported as deleted and inserted
+    /// wholesale, even if most of it is unchanged. Filling the gaps gives a
+    /// minimal diff of each such region, at the cost of Myers' O(ND) time
+    /// for the region. Moves are still only detected between unique lines.
+    pub fill_gaps: bool,
+
+    /// How many levels deep to look for new anchors within the gaps between
+    /// matched lines, before filling them; 0, the default, doesn't look.
+    ///
+    /// A line that occurs many times in the files may occur only once in a
+    /// gap, in which case it can anchor the lines around it like a unique
+    /// line does, splitting the gap into smaller ones to look in again. This
+    /// is how patience diff finds its matches, and is much cheaper than
+    /// filling the gaps, taking linear time per level. Unlike filling, it
+    /// can find moves within a gap.
+    pub anchor_depth: usize,
+}
+
+impl Heckel {
+    /// Diff the old sequence `O` against the new sequence `N`, like
+    /// [`diff_slices`].
+    pub fn diff<T: Hash + Eq>(&self, O: &[T], N: &[T]) -> Diff {
+        self.diff_with_hasher(O, N, RandomState::new())
+    }
+
+    /// Like [`diff`](Self::diff), but uses `hasher` to hash symbols in the
+    /// symbol table, like [`diff_slices_with_hasher`].
+    pub fn diff_with_hasher<T: Hash + Eq, S: BuildHasher>(
+        &self,
+        O: &[T],
+        N: &[T],
+        hasher: S,
+    ) -> Diff {
+        heckel(O, N, hasher, self)
+    }
+}
 
+fn heckel<T: Hash + Eq, S: BuildHasher>(O: &[T], N: &[T], hasher: S, options: &Heckel) -> Diff {
     // Symbol table, representing distinct lines in the old and new file
-    // and the number of occurrences in each.
-    let mut symbols: HashMap<u64, Rc<RefCell<SymbolEntry>>> = HashMap::new();
+    // and the number of occurrences in each. Entries live in a flat arena
+    // and are addressed by index; the map is keyed on the lines themselves
+    // (not just their hash), so distinct lines with colliding hashes still
+    // get distinct entries.
+    let mut symbols: HashMap<&T, usize, S> = HashMap::with_capacity_and_hasher(N.len(), hasher);
+    let mut entries: Vec<SymbolEntry> = Vec::with_capacity(N.len());
 
-    // Symbols contained in the old file.
-    let mut OA: Vec<Symbol> = Vec::new();
+    // Symbols contained in the old file, starting with the virtual BEGIN line.
+    let mut OA: Vec<Symbol> = Vec::with_capacity(O.len() + 2);
+    OA.push(Symbol::reference(0));
 
-    // Symbols contained in the new file.
-    let mut NA: Vec<Symbol> = Vec::new();
+    // Symbols contained in the new file, starting with the virtual BEGIN line.
+    let mut NA: Vec<Symbol> = Vec::with_capacity(N.len() + 2);
+    NA.push(Symbol::reference(0));
 
     // first pass
     //
@@ -85,52 +218,34 @@ pub fn heckel_diff<O: Read, N: Read>(O: O, N: N) -> eyre::Result<()> {
     // b) a symbol table entry for each line i is created if it does not already exist
     // c) NC for the line's symbol table entry is incremented
     // d) NA[i] is set to point to the symbol table entry of line i
-    for line in N.lines() {
-        let line = line?;
-        let hash = hash_str(&line);
-        let sym = symbols
-            .entry(hash)
-            .and_modify(|sym| sym.borrow_mut().NC.increment())
-            .or_insert_with(|| {
-                Rc::new(RefCell::new(SymbolEntry {
-                    OC: Occurrences::Zero,
-                    NC: Occurrences::One,
-                    OLNO: None,
-                    line,
-                }))
-            });
-        NA.push(Symbol::Entry(Rc::clone(sym)));
-    }
-
-    // eprintln!("first pass ===\nsymbols\n{symbols:?}\n\nNA\n{NA:#?}\n");
+    for line in N {
+        let index = *symbols.entry(line).or_insert_with(|| {
+            entries.push(SymbolEntry::default());
+            entries.len() - 1
+        });
+        entries[index].NC.increment();
+        NA.push(Symbol::entry(index));
+    }
+
+    // eprintln!("first pass ===\nsymbols\n{entries:?}\n\nNA\n{NA:?}\n");
 
     // second pass
     //
     // identical to the first pass, except we now act on O, OA, OC, and set OLNO
-    for (line_num, line) in O.lines().enumerate() {
+    for (line_num, line) in O.iter().enumerate() {
         // offset line number by 1 to accommodate virtual BEGIN line
         let line_num = line_num + 1;
-        let line = line?;
-        let hash = hash_str(&line);
-        let sym = symbols
-            .entry(hash)
-            .and_modify(|sym| {
-                let mut sym = sym.borrow_mut();
-                sym.OC.increment();
-                sym.OLNO = Some(line_num);
-            })
-            .or_insert_with(|| {
-                Rc::new(RefCell::new(SymbolEntry {
-                    OC: Occurrences::One,
-                    NC: Occurrences::Zero,
-                    OLNO: Some(line_num),
-                    line,
-                }))
-            });
-        OA.push(Symbol::Entry(Rc::clone(sym)));
+        let index = *symbols.entry(line).or_insert_with(|| {
+            entries.push(SymbolEntry::default());
+            entries.len() - 1
+        });
+        let entry = &mut entries[index];
+        entry.OC.increment();
+        entry.OLNO = line_num as u32;
+        OA.push(Symbol::entry(index));
     }
 
-    // eprintln!("second pass ===\nsymbols\n{symbols:?}\n\nOA\n{OA:#?}\n");
+    // eprintln!("second pass ===\nsymbols\n{entries:?}\n\nOA\n{OA:?}\n");
 
     // third pass
     //
@@ -138,66 +253,60 @@ pub fn heckel_diff<O: Read, N: Read>(O: O, N: N) -> eyre::Result<()> {
     // (we assume) the same unmodified line, replace the symbol table pointers with
     // a reference to the line in the other file
     for (line_num, sym) in NA.iter_mut().enumerate() {
-        // offset line number by 1 to accommodate virtual BEGIN line
-        let line_num = line_num + 1;
-        let entry = sym.as_entry_mut().borrow();
+        let Some(index) = sym.as_entry() else {
+            continue;
+        };
+        let entry = &entries[index];
         if entry.OC == Occurrences::One && entry.NC == Occurrences::One {
-            let OLNO = entry.OLNO.unwrap();
-            drop(entry);
-            *sym = Symbol::Reference(OLNO);
-            OA[OLNO - 1] = Symbol::Reference(line_num);
+            let OLNO = entry.OLNO as usize;
+            *sym = Symbol::reference(OLNO);
+            OA[OLNO] = Symbol::reference(line_num);
         }
     }
 
-    // add BEGIN lines
-    OA.insert(0, Symbol::Reference(0));
-    NA.insert(0, Symbol::Reference(0));
-
     // add END lines
-    OA.push(Symbol::Reference(NA.len()));
-    NA.push(Symbol::Reference(OA.len() - 1));
+    OA.push(Symbol::reference(NA.len()));
+    NA.push(Symbol::reference(OA.len() - 1));
+    let lines = 1..NA.len() - 1;
 
-    // eprintln!("third pass ===\nOA\n{OA:#?}\nNA\n{NA:#?}\n");
+    // eprintln!("third pass ===\nOA\n{OA:?}\nNA\n{NA:?}\n");
 
     // fourth pass
     //
     // use observation 2 to process each line in NA in ascending order:
     // if NA[i] points to OA[j] and NA[i + 1] and OA[j + 1] contain identical
     // symbol table entry pointers, then NA[i + 1] and OA[j + 1] refer to each other
-    for i in 0..(NA.len() - 1) {
-        if let Symbol::Reference(j) = NA[i] {
-            if let Symbol::Entry(ref entry_na) = NA[i + 1] {
-                if let Symbol::Entry(ref entry_oa) = OA[j + 1] {
-                    if Rc::ptr_eq(entry_na, entry_oa) {
-                        NA[i + 1] = Symbol::Reference(j + 1);
-                        OA[j + 1] = Symbol::Reference(i + 1);
-                    }
-                }
-            }
-        }
-    }
+    extend_forwards(&mut OA, &mut NA, 0..lines.end);
 
-    // eprintln!("fourth pass ===\nOA\n{OA:#?}\nNA\n{NA:#?}\n");
+    // eprintln!("fourth pass ===\nOA\n{OA:?}\nNA\n{NA:?}\n");
 
     // fifth pass
     //
     // like the fourth pass, use observation 2 but this time apply it in descending order:
     // if NA[i] points to OA[j] and NA[i - 1] and OA[j - 1] contain identical
     // symbol table entry pointers, then NA[i - 1] and OA[j - 1] refer to each other
-    for i in (1..(NA.len() - 1)).rev() {
-        if let Symbol::Reference(j) = NA[i] {
-            if let Symbol::Entry(ref entry_na) = NA[i - 1] {
-                if let Symbol::Entry(ref entry_oa) = OA[j - 1] {
-                    if Rc::ptr_eq(entry_na, entry_oa) {
-                        NA[i - 1] = Symbol::Reference(j - 1);
-                        OA[j - 1] = Symbol::Reference(i - 1);
-                    }
-                }
-            }
-        }
-    }
+    extend_backwards(&mut OA, &mut NA, lines.clone());
+
+    // eprintln!("fifth pass ===\nOA\n{OA:?}\nNA\n{NA:?}\n");
 
-    // eprintln!("fifth pass ===\nOA\n{OA:#?}\nNA\n{NA:#?}\n");
+    // optionally, match up the lines between anchors that the passes above
+    // couldn't: first by repeating the passes within each gap, where lines
+    // that occur many times in the files may occur only once, then by
+    // finding a longest common subsequence of what's left
+    if options.anchor_depth > 0 {
+        let mut counts = Vec::new();
+        counts.resize_with(entries.len(), SymbolEntry::default);
+        anchor_gaps(
+            &mut OA,
+            &mut NA,
+            lines.clone(),
+            &mut counts,
+            options.anchor_depth,
+        );
+    }
+    if options.fill_gaps {
+        fill_gaps(&mut OA, &mut NA, lines);
+    }
 
     // sixth pass
     //
@@ -205,12 +314,131 @@ pub fn heckel_diff<O: Read, N: Read>(O: O, N: N) -> eyre::Result<()> {
     // - if NA[i] points to a symbol table entry, assume that line i is an insert
     // - if NA[i] points to OA[j], but NA[i + 1] doesn't point to OA[j + 1], then
     //   line i is at the boundary of a deletion or block move
+    let new_refs: Vec<Option<usize>> = NA[1..NA.len() - 1]
+        .iter()
+        .map(|sym| sym.as_reference().map(|j| j - 1))
+        .collect();
+
+    Diff::from_references(OA.len() - 2, &new_refs)
+}
+
+/// The fourth pass over NA[lines]: extend each match forwards while the
+/// next lines in both files are the same unmatched symbol.
+fn extend_forwards(OA: &mut [Symbol], NA: &mut [Symbol], lines: Range<usize>) {
+    for i in lines {
+        if let Some(j) = NA[i].as_reference() {
+            if NA[i + 1].as_entry().is_some() && NA[i + 1] == OA[j + 1] {
+                NA[i + 1] = Symbol::reference(j + 1);
+                OA[j + 1] = Symbol::reference(i + 1);
+            }
+        }
+    }
+}
+
+/// The fifth pass over NA[lines]: extend each match backwards while the
+/// previous lines in both files are the same unmatched symbol.
+fn extend_backwards(OA: &mut [Symbol], NA: &mut [Symbol], lines: Range<usize>) {
+    for i in lines.rev() {
+        if let Some(j) = NA[i].as_reference() {
+            if NA[i - 1].as_entry().is_some() && NA[i - 1] == OA[j - 1] {
+                NA[i - 1] = Symbol::reference(j - 1);
+                OA[j - 1] = Symbol::reference(i - 1);
+            }
+        }
+    }
+}
+
+/// The gaps within NA[lines], whose neighbours must be matched: runs of
+/// unmatched lines between two matched lines that are next to each other in
+/// both files, with only unmatched lines between them in the old file too.
+/// Returned as pairs of ranges of OA and NA. Gaps on either side of a move
+/// are left out.
+fn gaps(OA: &[Symbol], NA: &[Symbol], lines: Range<usize>) -> Vec<(Range<usize>, Range<usize>)> {
+    let mut gaps = Vec::new();
+    let mut i = lines.start;
+    while i < lines.end {
+        if NA[i].as_entry().is_none() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while NA[i].as_entry().is_some() {
+            i += 1;
+        }
+        let (Some(before), Some(after)) = (NA[start - 1].as_reference(), NA[i].as_reference())
+        else {
+            unreachable!("runs of unmatched lines are surrounded by matched lines")
+        };
+        if after > before + 1
+            && OA[before + 1..after]
+                .iter()
+                .all(|sym| sym.as_entry().is_some())
+        {
+            gaps.push((before + 1..after, start..i));
+        }
+    }
+    gaps
+}
+
+/// Repeat the third, fourth and fifth passes within each gap of NA[lines],
+/// counting occurrences within the gap alone, then recurse into the gaps
+/// that leaves, up to `depth` levels deep. `counts` must be all zeroes, and
+/// is left that way.
+fn anchor_gaps(
+    OA: &mut [Symbol],
+    NA: &mut [Symbol],
+    lines: Range<usize>,
+    counts: &mut [SymbolEntry],
+    depth: usize,
+) {
+    if depth == 0 {
+        return;
+    }
+
+    for (old, new) in gaps(OA, NA, lines) {
+        let entry = |sym: Symbol| sym.as_entry().expect("gaps are unmatched");
+        for j in old.clone() {
+            let count = &mut counts[entry(OA[j])];
+            count.OC.increment();
+            count.OLNO = j as u32;
+        }
+        for i in new.clone() {
+            counts[entry(NA[i])].NC.increment();
+        }
+
+        let anchors: Vec<(usize, usize)> = new
+            .clone()
+            .filter_map(|i| {
+                let count = &counts[entry(NA[i])];
+                (count.OC == Occurrences::One && count.NC == Occurrences::One)
+                    .then_some((count.OLNO as usize, i))
+            })
+            .collect();
+        for &sym in OA[old.clone()].iter().chain(&NA[new.clone()]) {
+            counts[entry(sym)] = SymbolEntry::default();
+        }
+        if anchors.is_empty() {
+            continue;
+        }
 
-    Ok(())
+        for (j, i) in anchors {
+            NA[i] = Symbol::reference(j);
+            OA[j] = Symbol::reference(i);
+        }
+        extend_forwards(OA, NA, new.clone());
+        extend_backwards(OA, NA, new.clone());
+        anchor_gaps(OA, NA, new, counts, depth - 1);
+    }
 }
 
-fn hash_str(s: &str) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    hasher.write(s.as_bytes());
-    hasher.finish()
+/// Match up the unmatched lines in each gap of NA[lines] using a longest
+/// common subsequence.
+fn fill_gaps(OA: &mut [Symbol], NA: &mut [Symbol], lines: Range<usize>) {
+    for (old, new) in gaps(OA, NA, lines) {
+        for (j, i) in myers::lcs(&OA[old.clone()], &NA[new.clone()]) {
+            OA[old.start + j] = Symbol::reference(new.start + i);
+            NA[new.start + i] = Symbol::reference(old.start + j);
+        }
+    }
 }
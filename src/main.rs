@@ -1,12 +1,462 @@
-use heckel_diff::heckel_diff;
-use std::fs::File;
+use clap::builder::RangedU64ValueParser;
+use clap::{Parser, ValueEnum};
+use eyre::WrapErr;
+use heckel_diff::algorithm;
+use heckel_diff::cleanup::Cleanup;
+#[cfg(feature = "serde")]
+use heckel_diff::json;
+use heckel_diff::refine::Granularity;
+use heckel_diff::unified::{self, Header};
+use heckel_diff::{context, ed, html, normal, rcs, side_by_side};
+use heckel_diff::{DiffOptions, Heckel, Palette, Text, TextDiff};
+use regex::bytes::Regex;
+use std::env;
+use std::fs;
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::SystemTime;
 
-fn main() -> eyre::Result<()> {
-    color_eyre::install()?;
+/// Compare files line by line using Paul Heckel's diff algorithm.
+///
+/// Exits with 0 if the files are identical, 1 if they differ, and 2 if
+/// something went wrong.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Cli {
+    /// The old file, or `-` for standard input
+    old: PathBuf,
 
-    let left = File::open("assets/left.txt")?;
-    let right = File::open("assets/right.txt")?;
-    heckel_diff(&left, &right)?;
+    /// The new file, or `-` for standard input
+    new: PathBuf,
 
-    Ok(())
+    /// The output format
+    #[arg(long, value_enum, default_value_t = Format::Unified)]
+    format: Format,
+
+    /// Output NUM (default 3) lines of unified context, with file times in UTC
+    #[arg(
+        short = 'u',
+        long = "unified",
+        value_name = "NUM",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "3"
+    )]
+    unified: Option<usize>,
+
+    /// Output NUM lines of unified context, with file times in UTC
+    #[arg(short = 'U', value_name = "NUM")]
+    unified_lines: Option<usize>,
+
+    /// Output NUM (default 3) lines of copied context, with file times in UTC
+    #[arg(
+        short = 'c',
+        long = "context",
+        value_name = "NUM",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "3",
+        conflicts_with_all = ["format", "unified", "unified_lines"]
+    )]
+    context: Option<usize>,
+
+    /// Output NUM lines of copied context, with file times in UTC
+    #[arg(
+        short = 'C',
+        value_name = "NUM",
+        conflicts_with_all = ["format", "unified", "unified_lines"]
+    )]
+    context_lines: Option<usize>,
+
+    /// Output a normal diff, GNU diff's default format
+    #[arg(
+        long,
+        conflicts_with_all = ["format", "unified", "unified_lines", "context", "context_lines"]
+    )]
+    normal: bool,
+
+    /// Output an ed script
+    #[arg(
+        short = 'e',
+        long,
+        conflicts_with_all = ["format", "unified", "unified_lines", "context", "context_lines", "normal"]
+    )]
+    ed: bool,
+
+    /// Output an RCS format diff
+    #[arg(
+        short = 'n',
+        long,
+        conflicts_with_all = ["format", "unified", "unified_lines", "context", "context_lines", "normal", "ed"]
+    )]
+    rcs: bool,
+
+    /// Output in two columns
+    #[arg(
+        short = 'y',
+        long,
+        conflicts_with_all = ["format", "unified", "unified_lines", "context", "context_lines", "normal", "ed", "rcs"]
+    )]
+    side_by_side: bool,
+
+    /// Output at most NUM (default 130) print columns, for side-by-side
+    /// output
+    #[arg(short = 'W', long, value_name = "NUM", default_value_t = 130, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    width: usize,
+
+    /// Expand tabs to spaces in side-by-side output
+    #[arg(short = 't', long)]
+    expand_tabs: bool,
+
+    /// Tab stops every NUM (default 8) print columns
+    #[arg(long = "tabsize", value_name = "NUM", default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+    tab_size: u16,
+
+    /// Output only the left column of common lines in side-by-side output
+    #[arg(long)]
+    left_column: bool,
+
+    /// Do not output common lines in side-by-side output
+    #[arg(long)]
+    suppress_common_lines: bool,
+
+    /// Treat all files as text, even if they look like binary files
+    #[arg(short = 'a', long)]
+    text: bool,
+
+    /// Treat `\r\n` line endings the same as `\n`
+    #[arg(long)]
+    strip_trailing_cr: bool,
+
+    /// Ignore all white space
+    #[arg(short = 'w', long)]
+    ignore_all_space: bool,
+
+    /// Ignore changes in the amount of white space
+    #[arg(short = 'b', long)]
+    ignore_space_change: bool,
+
+    /// Ignore white space at the end of lines
+    #[arg(short = 'Z', long)]
+    ignore_trailing_space: bool,
+
+    /// Ignore case differences
+    #[arg(short = 'i', long)]
+    ignore_case: bool,
+
+    /// Ignore changes that only insert or delete blank lines
+    #[arg(short = 'B', long)]
+    ignore_blank_lines: bool,
+
+    /// Ignore changes that only insert or delete lines matching REGEX (can
+    /// be repeated)
+    #[arg(short = 'I', long, value_name = "REGEX")]
+    ignore_matching_lines: Vec<Regex>,
+
+    /// Mask the parts of lines matching REGEX before comparing them (can be
+    /// repeated)
+    #[arg(long, value_name = "REGEX")]
+    mask: Vec<Regex>,
+
+    /// The diff algorithm to use
+    #[arg(long, value_enum, default_value_t = Algorithm::Heckel)]
+    algorithm: Algorithm,
+
+    /// Match up repeated lines between unique ones too, giving a smaller
+    /// diff when many lines occur more than once (for --algorithm=heckel)
+    #[arg(short = 'd', long)]
+    minimal: bool,
+
+    /// Slide blocks of changed lines to line up with blank lines and
+    /// indentation, like git's indent heuristic
+    #[arg(long)]
+    indent_heuristic: bool,
+
+    /// Merge runs of up to NUM unchanged lines between changes into the
+    /// changes, when they're no longer than the changes on either side
+    #[arg(long, value_name = "NUM", default_value_t = 0)]
+    merge_equalities: usize,
+
+    /// Use LABEL instead of the file name and timestamp (can be given twice)
+    #[arg(short = 'L', long = "label", value_name = "LABEL", action = clap::ArgAction::Append)]
+    labels: Vec<String>,
+
+    /// Colorize the output
+    #[arg(
+        long,
+        value_enum,
+        value_name = "WHEN",
+        default_value_t = Color::Auto,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "auto"
+    )]
+    color: Color,
+
+    /// The colors to use with --color, e.g. `ad=1;32:de=1;31` (see
+    /// `heckel_diff::Palette` for the keys)
+    #[arg(long, value_name = "PALETTE")]
+    palette: Option<Palette>,
+
+    /// Highlight the words (or characters, or graphemes) that changed within
+    /// changed lines, in colored and HTML output (HTML highlights words by default)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "UNIT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "word"
+    )]
+    refine: Option<Refine>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// `diff -u` style output
+    Unified,
+
+    /// `diff -c` style output
+    Context,
+
+    /// `diff` style output, without any context
+    Normal,
+
+    /// `diff -e` style ed script
+    Ed,
+
+    /// `diff -n` style RCS output
+    Rcs,
+
+    /// `diff -y` style two column output
+    SideBySide,
+
+    /// A self-contained HTML report
+    Html,
+
+    /// The diff as JSON data
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Algorithm {
+    /// Paul Heckel's algorithm, which also finds moved blocks
+    Heckel,
+
+    /// Myers' algorithm, for the smallest diff
+    Myers,
+
+    /// Patience diff, as in `git diff --patience`
+    Patience,
+
+    /// Histogram diff, as in `git diff --histogram`
+    Histogram,
+}
+
+impl Algorithm {
+    fn algorithm(self, heckel: Heckel) -> algorithm::Algorithm {
+        match self {
+            Self::Heckel => algorithm::Algorithm::Heckel(heckel),
+            Self::Myers => algorithm::Algorithm::Myers,
+            Self::Patience => algorithm::Algorithm::Patience,
+            Self::Histogram => algorithm::Algorithm::Histogram,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Refine {
+    None,
+    Word,
+    Char,
+    Grapheme,
+}
+
+impl Refine {
+    fn granularity(self) -> Option<Granularity> {
+        match self {
+            Self::None => None,
+            Self::Word => Some(Granularity::Word),
+            Self::Char => Some(Granularity::Char),
+            Self::Grapheme => Some(Granularity::Grapheme),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Color {
+    /// Only colorize output to a terminal, unless `NO_COLOR` is set or
+    /// `TERM` is `dumb`
+    Auto,
+    Always,
+    Never,
+}
+
+fn main() -> ExitCode {
+    // clap exits with status 2 on usage errors, matching GNU diff
+    let cli = Cli::parse();
+
+    match run(cli) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(err) => {
+            eprintln!("heckel-diff: {err:#}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Diff the files named on the command line, returning whether they're
+/// identical.
+fn run(cli: Cli) -> eyre::Result<bool> {
+    if cli.labels.len() > 2 {
+        eyre::bail!("--label can be given at most twice");
+    }
+
+    let (old, old_header) = read(&cli.old, cli.labels.first())?;
+    let (new, new_header) = if cli.old == Path::new("-") && cli.new == Path::new("-") {
+        // standard input can only be read once, and like GNU diff, comparing
+        // it with itself finds no differences
+        let header = match cli.labels.get(1) {
+            Some(label) => Header::new(label),
+            None => old_header.clone(),
+        };
+        (old.clone(), header)
+    } else {
+        read(&cli.new, cli.labels.get(1))?
+    };
+    let (old, new) = (Text::new(old), Text::new(new));
+
+    // like GNU diff, only report whether binary files differ
+    if !cli.text && (old.is_binary() || new.is_binary()) {
+        let identical = old.as_bytes() == new.as_bytes();
+        if !identical {
+            println!(
+                "Binary files {} and {} differ",
+                old_header.label, new_header.label
+            );
+        }
+        return Ok(identical);
+    }
+
+    let options = DiffOptions {
+        strip_trailing_cr: cli.strip_trailing_cr,
+        ignore_all_space: cli.ignore_all_space,
+        ignore_space_change: cli.ignore_space_change,
+        ignore_trailing_space: cli.ignore_trailing_space,
+        ignore_case: cli.ignore_case,
+        ignore_blank_lines: cli.ignore_blank_lines,
+        ignore_matching_lines: cli.ignore_matching_lines,
+        mask: cli.mask,
+    };
+    let algorithm = cli.algorithm.algorithm(Heckel {
+        fill_gaps: cli.minimal,
+        ..Default::default()
+    });
+    let mut diff = TextDiff::with_algorithm(old, new, &options, &algorithm);
+    diff.clean_up(&Cleanup {
+        slide: cli.indent_heuristic,
+        merge_equalities: cli.merge_equalities,
+    });
+
+    let color = match cli.color {
+        Color::Auto => {
+            io::stdout().is_terminal()
+                && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                && env::var_os("TERM").is_none_or(|term| term != "dumb")
+        }
+        Color::Always => true,
+        Color::Never => false,
+    };
+    let color = color.then(|| cli.palette.unwrap_or_default());
+
+    let format = if cli.normal {
+        Format::Normal
+    } else if cli.ed {
+        Format::Ed
+    } else if cli.rcs {
+        Format::Rcs
+    } else if cli.side_by_side {
+        Format::SideBySide
+    } else if cli.context.is_some() || cli.context_lines.is_some() {
+        Format::Context
+    } else {
+        cli.format
+    };
+    let lines = cli
+        .context_lines
+        .or(cli.context)
+        .or(cli.unified_lines)
+        .or(cli.unified);
+    let refine = cli.refine.and_then(Refine::granularity);
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    match format {
+        Format::Unified | Format::Context => {
+            let mut options = unified::Options::new(old_header, new_header);
+            options.context = lines.unwrap_or(options.context);
+            options.color = color;
+            options.refine = refine;
+            match format {
+                Format::Unified => unified::write(&mut out, &diff, &options)?,
+                _ => context::write(&mut out, &diff, &options)?,
+            }
+        }
+        Format::Normal => {
+            let options = normal::Options { color, refine };
+            normal::write(&mut out, &diff, &options)?;
+        }
+        Format::Ed => ed::write(&mut out, &diff)?,
+        Format::Rcs => rcs::write(&mut out, &diff)?,
+        Format::Html => {
+            let mut options = html::Options::new(old_header, new_header);
+            options.context = lines.unwrap_or(options.context);
+            if let Some(unit) = cli.refine {
+                options.refine = unit.granularity();
+            }
+            html::write(&mut out, &diff, &options)?;
+        }
+        #[cfg(feature = "serde")]
+        Format::Json => {
+            let options = json::Options::new(old_header.label, new_header.label);
+            json::write(&mut out, &diff, &options)?;
+        }
+        Format::SideBySide => {
+            let options = side_by_side::Options {
+                width: cli.width,
+                tab_size: cli.tab_size.into(),
+                expand_tabs: cli.expand_tabs,
+                left_column: cli.left_column,
+                suppress_common_lines: cli.suppress_common_lines,
+                color,
+                refine,
+            };
+            side_by_side::write(&mut out, &diff, &options)?;
+        }
+    }
+    out.flush()?;
+
+    Ok(diff.is_identical())
+}
+
+/// Read `path` (or standard input, for `-`), along with the header to print
+/// for it.
+fn read(path: &Path, label: Option<&String>) -> eyre::Result<(Vec<u8>, Header)> {
+    let (contents, modified) = if path == Path::new("-") {
+        let mut contents = Vec::new();
+        io::stdin().read_to_end(&mut contents)?;
+        (contents, SystemTime::now())
+    } else {
+        let contents =
+            fs::read(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        (contents, fs::metadata(path)?.modified()?)
+    };
+
+    let header = match label {
+        Some(label) => Header::new(label),
+        None => Header::new(path.display().to_string()).modified(modified),
+    };
+    Ok((contents, header))
 }
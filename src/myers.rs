@@ -0,0 +1,174 @@
+use std::ops::{Index, IndexMut, Range};
+
+/// Find a longest common subsequence of `a` and `b` with Myers' O(ND)
+/// algorithm, in its linear space form, returning the pairs of matching
+/// elements in order.
+///
+/// See Eugene W. Myers, "An O(ND) Difference Algorithm and Its Variations",
+/// Algorithmica 1 (1986).
+pub(crate) fn lcs<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let max = (a.len() + b.len()).div_ceil(2) + 1;
+    let mut forward = V::new(max);
+    let mut backward = V::new(max);
+    let mut matches = Vec::new();
+    conquer(
+        a,
+        0..a.len(),
+        b,
+        0..b.len(),
+        &mut forward,
+        &mut backward,
+        &mut matches,
+    );
+    matches
+}
+
+/// The furthest reaching x on each diagonal k, indexed by k from -max to
+/// max.
+struct V {
+    offset: isize,
+    v: Vec<usize>,
+}
+
+impl V {
+    fn new(max: usize) -> Self {
+        Self {
+            offset: max as isize + 1,
+            v: vec![0; 2 * max + 3],
+        }
+    }
+}
+
+impl Index<isize> for V {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &usize {
+        &self.v[(k + self.offset) as usize]
+    }
+}
+
+impl IndexMut<isize> for V {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.v[(k + self.offset) as usize]
+    }
+}
+
+/// Match up `a[a_range]` and `b[b_range]`, appending the matches to
+/// `matches`: strip their common prefix and suffix, then split them at the
+/// middle of a shortest edit script and recurse on each half.
+fn conquer<T: PartialEq>(
+    a: &[T],
+    mut a_range: Range<usize>,
+    b: &[T],
+    mut b_range: Range<usize>,
+    forward: &mut V,
+    backward: &mut V,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    let prefix = common_prefix(&a[a_range.clone()], &b[b_range.clone()]);
+    matches.extend((0..prefix).map(|k| (a_range.start + k, b_range.start + k)));
+    a_range.start += prefix;
+    b_range.start += prefix;
+
+    let suffix = common_suffix(&a[a_range.clone()], &b[b_range.clone()]);
+    a_range.end -= suffix;
+    b_range.end -= suffix;
+
+    if !a_range.is_empty() && !b_range.is_empty() {
+        let (x, y) = middle_snake(a, a_range.clone(), b, b_range.clone(), forward, backward);
+        conquer(
+            a,
+            a_range.start..x,
+            b,
+            b_range.start..y,
+            forward,
+            backward,
+            matches,
+        );
+        conquer(
+            a,
+            x..a_range.end,
+            b,
+            y..b_range.end,
+            forward,
+            backward,
+            matches,
+        );
+    }
+    matches.extend((0..suffix).map(|k| (a_range.end + k, b_range.end + k)));
+}
+
+/// Find a point on a shortest edit script from the start of `a[a_range]`
+/// and `b[b_range]` to their end, around its middle, by searching forwards
+/// from the start and backwards from the end until the searches meet. The
+/// ranges must be non-empty and must not start or end with a match, which
+/// keeps the point away from either end.
+fn middle_snake<T: PartialEq>(
+    a: &[T],
+    a_range: Range<usize>,
+    b: &[T],
+    b_range: Range<usize>,
+    forward: &mut V,
+    backward: &mut V,
+) -> (usize, usize) {
+    let (a, b) = (&a[a_range.clone()], &b[b_range.clone()]);
+    let (n, m) = (a.len(), b.len());
+    let delta = n as isize - m as isize;
+    let odd = delta % 2 != 0;
+    forward[1] = 0;
+    backward[1] = 0;
+
+    for d in 0..=((n + m).div_ceil(2) as isize) {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && forward[k - 1] < forward[k + 1]) {
+                forward[k + 1]
+            } else {
+                forward[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            let start = (x, y);
+            if x < n && y < m {
+                let snake = common_prefix(&a[x..], &b[y..]);
+                x += snake;
+                y += snake;
+            }
+            forward[k] = x;
+            if odd && (k - delta).abs() < d && x <= n && y <= m && x + backward[delta - k] >= n {
+                return (a_range.start + start.0, b_range.start + start.1);
+            }
+        }
+
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && backward[k - 1] < backward[k + 1]) {
+                backward[k + 1]
+            } else {
+                backward[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            if x < n && y < m {
+                let snake = common_suffix(&a[..n - x], &b[..m - y]);
+                x += snake;
+                y += snake;
+            }
+            backward[k] = x;
+            if !odd && (k - delta).abs() <= d && x <= n && y <= m && x + forward[delta - k] >= n {
+                return (a_range.start + n - x, b_range.start + m - y);
+            }
+        }
+    }
+    unreachable!("the searches always meet")
+}
+
+/// The number of elements `a` and `b` start with in common.
+fn common_prefix<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+/// The number of elements `a` and `b` end with in common.
+fn common_suffix<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    a.iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count()
+}
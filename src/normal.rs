@@ -0,0 +1,77 @@
+//! Normal output, the format GNU diff uses by default: a command like `3c3`,
+//! `5,7d4` or `8a9,10` for each change, followed by the deleted lines
+//! prefixed with `<` and the inserted lines prefixed with `>`.
+
+use crate::output::{write_line, write_refined_line, LineRange, Moves, Spans, Style};
+use crate::refine::Granularity;
+use crate::{LineEnding, Op, Palette, TextDiff};
+use std::io::{self, Write};
+
+/// Options for normal output.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options {
+    /// The colors to colorize the output with, using ANSI escape sequences,
+    /// or `None` to leave it uncolored.
+    pub color: Option<Palette>,
+
+    /// Whether to highlight the parts of changed lines that changed, when
+    /// the output is colored, and how finely to split lines to find them.
+    pub refine: Option<Granularity>,
+}
+
+/// Write `diff` to `out` in normal format. Nothing is written if the files
+/// are identical.
+pub fn write<W: Write>(out: &mut W, diff: &TextDiff, options: &Options) -> io::Result<()> {
+    let palette = options.color.as_ref();
+    let moves = Moves::new(diff.diff());
+    let spans = Spans::new(diff, options.refine.filter(|_| palette.is_some()));
+    for hunk in diff.hunks(0) {
+        let (old, new) = (hunk.old_range(), hunk.new_range());
+        let command = match (old.is_empty(), new.is_empty()) {
+            (true, _) => format!("{}a{}", old.start, LineRange(new)),
+            (_, true) => format!("{}d{}", LineRange(old), new.start),
+            _ => format!("{}c{}", LineRange(old), LineRange(new)),
+        };
+        write_line(
+            out,
+            palette,
+            Style::Hunk,
+            b"",
+            command.as_bytes(),
+            LineEnding::Lf,
+        )?;
+
+        let text = diff.old_text();
+        for op in hunk.ops() {
+            if let Op::Delete { old, .. } = op {
+                for i in old.clone() {
+                    let style = Style::deleted(&moves, i);
+                    let (line, ending) = (text.line(i), text.line_ending(i));
+                    write_refined_line(out, palette, style, b"< ", line, spans.of_old(i), ending)?;
+                }
+            }
+        }
+        if !hunk.old_range().is_empty() && !hunk.new_range().is_empty() {
+            out.write_all(b"---\n")?;
+        }
+        let text = diff.new_text();
+        for op in hunk.ops() {
+            if let Op::Insert { new, .. } = op {
+                for i in new.clone() {
+                    let style = Style::inserted(&moves, i);
+                    let (line, ending) = (text.line(i), text.line_ending(i));
+                    write_refined_line(out, palette, style, b"> ", line, spans.of_new(i), ending)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Render `diff` in normal format. Lines are copied byte for byte, so the
+/// output is only valid UTF-8 if the files are.
+pub fn render(diff: &TextDiff, options: &Options) -> Vec<u8> {
+    let mut out = Vec::new();
+    write(&mut out, diff, options).expect("writing to a Vec never fails");
+    out
+}
@@ -0,0 +1,180 @@
+use crate::LineEnding;
+use regex::bytes::Regex;
+use std::borrow::Cow;
+
+/// What masked spans of a line are replaced with in its key. NUL bytes in the
+/// line itself are escaped as [`ESCAPED_NUL`], so a masked span can't be
+/// confused with real text.
+const MASK: &[u8] = b"\0\0";
+
+/// What NUL bytes are replaced with in the key of a line, when masking.
+const ESCAPED_NUL: &[u8] = b"\0\x01";
+
+/// Decides which lines are considered equal when diffing text, and which
+/// changes are too unimportant to report.
+pub trait Normalizer {
+    /// Map `line` (including its terminator) to the key it's compared by.
+    /// Lines with equal keys are considered equal. The original line is
+    /// still what ends up in the output.
+    fn normalize<'a>(&self, line: &'a [u8]) -> Cow<'a, [u8]>;
+
+    /// Whether a change that only deletes or inserts lines like `line` can be
+    /// left out of the output.
+    fn is_ignorable(&self, _line: &[u8]) -> bool {
+        false
+    }
+}
+
+/// Options controlling how lines are compared, mirroring GNU diff's.
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Treat `\r\n` line endings the same as `\n`, like GNU diff's
+    /// `--strip-trailing-cr`. A missing newline at the end of the file is
+    /// still reported.
+    pub strip_trailing_cr: bool,
+
+    /// Ignore all white space (`-w`).
+    pub ignore_all_space: bool,
+
+    /// Ignore changes in the amount of white space (`-b`).
+    pub ignore_space_change: bool,
+
+    /// Ignore white space at the end of lines (`-Z`).
+    pub ignore_trailing_space: bool,
+
+    /// Ignore case differences (`-i`).
+    pub ignore_case: bool,
+
+    /// Ignore changes that only insert or delete blank lines (`-B`).
+    pub ignore_blank_lines: bool,
+
+    /// Ignore changes that only insert or delete lines matching one of these
+    /// patterns (`-I`).
+    pub ignore_matching_lines: Vec<Regex>,
+
+    /// Mask the spans of each line matching one of these patterns before
+    /// comparing it, so e.g. lines that only differ in a timestamp are
+    /// considered equal.
+    pub mask: Vec<Regex>,
+}
+
+impl DiffOptions {
+    /// Normalize the contents of a line, without its terminator.
+    fn normalize_content<'a>(&self, content: &'a [u8]) -> Cow<'a, [u8]> {
+        let mut key = self.mask(content);
+
+        if self.ignore_all_space {
+            key = Cow::Owned(key.iter().copied().filter(|&b| !is_space(b)).collect());
+        } else if self.ignore_space_change {
+            // collapse each run of white space into a single space, and drop
+            // it entirely at the end of the line
+            let mut collapsed = Vec::with_capacity(key.len());
+            let mut space = false;
+            for &b in key.iter() {
+                if is_space(b) {
+                    space = true;
+                    continue;
+                }
+                if space {
+                    collapsed.push(b' ');
+                    space = false;
+                }
+                collapsed.push(b);
+            }
+            key = Cow::Owned(collapsed);
+        } else if self.ignore_trailing_space {
+            let end = key.iter().rposition(|&b| !is_space(b)).map_or(0, |i| i + 1);
+            match &mut key {
+                Cow::Borrowed(key) => *key = &key[..end],
+                Cow::Owned(key) => key.truncate(end),
+            }
+        }
+
+        if self.ignore_case {
+            key = Cow::Owned(match std::str::from_utf8(&key) {
+                Ok(key) => key.to_lowercase().into_bytes(),
+                Err(_) => key.to_ascii_lowercase(),
+            });
+        }
+        key
+    }
+
+    /// Replace each span of `content` matching one of the mask patterns with
+    /// [`MASK`], merging spans that overlap.
+    fn mask<'a>(&self, content: &'a [u8]) -> Cow<'a, [u8]> {
+        if self.mask.is_empty() {
+            return Cow::Borrowed(content);
+        }
+        let mut spans: Vec<_> = self
+            .mask
+            .iter()
+            .flat_map(|pattern| pattern.find_iter(content).map(|m| m.range()))
+            .collect();
+        if spans.is_empty() && !content.contains(&0) {
+            return Cow::Borrowed(content);
+        }
+        spans.sort_by_key(|span| span.start);
+
+        let mut key = Vec::with_capacity(content.len());
+        let mut end = 0;
+        for span in spans {
+            if span.start < end {
+                end = end.max(span.end);
+                continue;
+            }
+            escape(&content[end..span.start], &mut key);
+            key.extend_from_slice(MASK);
+            end = span.end;
+        }
+        escape(&content[end..], &mut key);
+        Cow::Owned(key)
+    }
+}
+
+/// Append `text` to `key`, escaping its NUL bytes.
+fn escape(text: &[u8], key: &mut Vec<u8>) {
+    for &b in text {
+        match b {
+            0 => key.extend_from_slice(ESCAPED_NUL),
+            b => key.push(b),
+        }
+    }
+}
+
+impl Normalizer for DiffOptions {
+    fn normalize<'a>(&self, line: &'a [u8]) -> Cow<'a, [u8]> {
+        // like GNU diff, the white space options treat the line ending as
+        // trailing white space, so it's ignored altogether
+        let (content, ending) = LineEnding::split(line);
+        let ignore_ending =
+            self.ignore_all_space || self.ignore_space_change || self.ignore_trailing_space;
+        let key_ending = match ending {
+            _ if ignore_ending => LineEnding::Lf,
+            LineEnding::CrLf if self.strip_trailing_cr => LineEnding::Lf,
+            ending => ending,
+        };
+
+        // only copy the line if normalizing actually changed it
+        match self.normalize_content(content) {
+            Cow::Borrowed(key) if key.len() == content.len() && key_ending == ending => {
+                Cow::Borrowed(line)
+            }
+            key => Cow::Owned([&key, key_ending.as_bytes()].concat()),
+        }
+    }
+
+    fn is_ignorable(&self, line: &[u8]) -> bool {
+        let content = LineEnding::split(line).0;
+        (self.ignore_blank_lines && self.normalize_content(content).is_empty())
+            || self
+                .ignore_matching_lines
+                .iter()
+                .any(|pattern| pattern.is_match(content))
+    }
+}
+
+/// Whether `b` is white space, as far as the white space options are
+/// concerned. Newlines never are, since they're part of the line ending.
+fn is_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r' | b'\x0b' | b'\x0c')
+}
@@ -0,0 +1,288 @@
+//! Building blocks shared by the output formats.
+
+use crate::refine::{Granularity, LinePair};
+use crate::{Diff, LineEnding, Op, Palette, TextDiff};
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// A timestamp formatted the way GNU diff does, but always in UTC rather
+/// than local time, which would need the system's time zone data. Unified
+/// diffs use e.g. `2024-01-31 12:34:56.000000000 +0000`, while context
+/// diffs use the traditional `Wed Jan 31 12:34:56 2024`.
+pub(crate) struct Timestamp {
+    pub time: SystemTime,
+    pub traditional: bool,
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (secs, nanos) = match self.time.duration_since(UNIX_EPOCH) {
+            Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+            Err(err) => {
+                let before = err.duration();
+                match before.subsec_nanos() {
+                    0 => (-(before.as_secs() as i64), 0),
+                    n => (-(before.as_secs() as i64) - 1, 1_000_000_000 - n),
+                }
+            }
+        };
+        let (days, secs) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+        let (hour, minute, second) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+        // convert days since the epoch into a civil date, see
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        if self.traditional {
+            // the epoch was a Thursday
+            let weekday = WEEKDAYS[(days + 4).rem_euclid(7) as usize];
+            let month = MONTHS[month as usize - 1];
+            write!(
+                f,
+                "{weekday} {month} {day:2} {hour:02}:{minute:02}:{second:02} {year}"
+            )
+        } else {
+            write!(
+                f,
+                "{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{second:02}.{nanos:09} +0000"
+            )
+        }
+    }
+}
+
+/// What a line of output is, for picking its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Style {
+    Plain,
+    Header,
+    Hunk,
+    Delete,
+    Insert,
+
+    /// A line where moved block `k` was moved from.
+    MovedFrom(usize),
+
+    /// A line where moved block `k` was moved to.
+    MovedTo(usize),
+}
+
+impl Style {
+    /// The style of deleted line `i` of the old file.
+    pub fn deleted(moves: &Moves, i: usize) -> Self {
+        moves.of_old(i).map_or(Self::Delete, Self::MovedFrom)
+    }
+
+    /// The style of inserted line `j` of the new file.
+    pub fn inserted(moves: &Moves, j: usize) -> Self {
+        moves.of_new(j).map_or(Self::Insert, Self::MovedTo)
+    }
+
+    /// The SGR parameters for this style in `palette`, if it's colored.
+    pub fn sgr(self, palette: &Palette) -> Option<&str> {
+        let sgr = match self {
+            Self::Plain => return None,
+            Self::Header => &palette.header,
+            Self::Hunk => &palette.hunk,
+            Self::Delete => &palette.delete,
+            Self::Insert => &palette.insert,
+            Self::MovedFrom(k) if k % 2 == 0 => &palette.moved_from,
+            Self::MovedFrom(_) => &palette.moved_from_alt,
+            Self::MovedTo(k) if k % 2 == 0 => &palette.moved_to,
+            Self::MovedTo(_) => &palette.moved_to_alt,
+        };
+        (!sgr.is_empty()).then_some(sgr)
+    }
+}
+
+/// Write a single line, colored according to `style` if a palette is given.
+/// The line keeps its original terminator, so patches reproduce the exact
+/// bytes of the file; lines missing one are marked like GNU diff does.
+pub(crate) fn write_line<W: Write>(
+    out: &mut W,
+    palette: Option<&Palette>,
+    style: Style,
+    prefix: &[u8],
+    line: &[u8],
+    ending: LineEnding,
+) -> io::Result<()> {
+    write_refined_line(out, palette, style, prefix, line, &[], ending)
+}
+
+/// Like [`write_line`], but with the byte ranges `spans` of the line, the
+/// parts that changed, highlighted too.
+pub(crate) fn write_refined_line<W: Write>(
+    out: &mut W,
+    palette: Option<&Palette>,
+    style: Style,
+    prefix: &[u8],
+    line: &[u8],
+    spans: &[Range<usize>],
+    ending: LineEnding,
+) -> io::Result<()> {
+    match palette.and_then(|palette| Some((palette, style.sgr(palette)?))) {
+        Some((palette, sgr)) => {
+            let reset = &palette.reset;
+            write!(out, "\x1b[{sgr}m")?;
+            out.write_all(prefix)?;
+            let mut end = 0;
+            for span in spans.iter().filter(|_| !palette.highlight.is_empty()) {
+                out.write_all(&line[end..span.start])?;
+                write!(out, "\x1b[{}m", palette.highlight)?;
+                out.write_all(&line[span.clone()])?;
+                write!(out, "\x1b[{reset}m")?;
+                end = span.end;
+                if end < line.len() {
+                    write!(out, "\x1b[{sgr}m")?;
+                }
+            }
+            // a highlight running to the end of the line has already reset
+            // the colors
+            if end == 0 || end < line.len() {
+                out.write_all(&line[end..])?;
+                write!(out, "\x1b[{reset}m")?;
+            }
+        }
+        None => {
+            out.write_all(prefix)?;
+            out.write_all(line)?;
+        }
+    }
+    match ending {
+        LineEnding::Missing => out.write_all(b"\n\\ No newline at end of file\n"),
+        ending => out.write_all(ending.as_bytes()),
+    }
+}
+
+/// A range of lines, as written by context and normal diffs: the first and
+/// last line, or just the line for ranges of a single line. Empty ranges are
+/// identified by the line before them.
+pub(crate) struct LineRange(pub Range<usize>);
+
+impl fmt::Display for LineRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.len() {
+            0 => write!(f, "{}", self.0.start),
+            1 => write!(f, "{}", self.0.start + 1),
+            _ => write!(f, "{},{}", self.0.start + 1, self.0.end),
+        }
+    }
+}
+
+/// The moved block each line belongs to, if any, for renderers that
+/// highlight moves. Blocks are numbered in the order of the new file.
+pub(crate) struct Moves {
+    old: Vec<Option<usize>>,
+    new: Vec<Option<usize>>,
+
+    /// Where each block starts in the old and new file.
+    starts: Vec<(usize, usize)>,
+}
+
+impl Moves {
+    pub fn new(diff: &Diff) -> Self {
+        let mut old = vec![None; diff.old_len()];
+        let mut new = vec![None; diff.new_len()];
+        let mut starts = Vec::new();
+        for (k, op) in diff.moves().enumerate() {
+            old[op.old_range()].fill(Some(k));
+            new[op.new_range()].fill(Some(k));
+            starts.push((op.old_range().start, op.new_range().start));
+        }
+        Self { old, new, starts }
+    }
+
+    /// Where block `k` starts in the old and new file.
+    pub fn start(&self, k: usize) -> (usize, usize) {
+        self.starts[k]
+    }
+
+    /// The block line `i` of the old file was moved with.
+    pub fn of_old(&self, i: usize) -> Option<usize> {
+        self.old[i]
+    }
+
+    /// The block line `j` of the new file was moved with.
+    pub fn of_new(&self, j: usize) -> Option<usize> {
+        self.new[j]
+    }
+}
+
+/// The changed spans of each line paired with another by refinement, for
+/// renderers that highlight them.
+#[derive(Default)]
+pub(crate) struct Spans {
+    pairs: Vec<LinePair>,
+}
+
+impl Spans {
+    /// Refine the changed lines of `diff` at `granularity`, if given.
+    pub fn new(diff: &TextDiff, granularity: Option<Granularity>) -> Self {
+        Self {
+            pairs: granularity.map(|g| diff.refine(&g)).unwrap_or_default(),
+        }
+    }
+
+    /// The changed spans of line `i` of the old file, if it was paired.
+    pub fn of_old(&self, i: usize) -> &[Range<usize>] {
+        match self.pairs.binary_search_by_key(&i, |pair| pair.old) {
+            Ok(k) => &self.pairs[k].refinement.old,
+            Err(_) => &[],
+        }
+    }
+
+    /// The changed spans of line `j` of the new file, if it was paired.
+    pub fn of_new(&self, j: usize) -> &[Range<usize>] {
+        match self.pairs.binary_search_by_key(&j, |pair| pair.new) {
+            Ok(k) => &self.pairs[k].refinement.new,
+            Err(_) => &[],
+        }
+    }
+}
+
+/// Pair up the lines deleted and inserted by `ops`, a run of changes, in
+/// order, so each deleted line can be shown next to the line that replaced
+/// it. Lines left over, and moved lines, which weren't changed, are paired
+/// with nothing.
+pub(crate) fn pair_changes(ops: &[Op], moves: &Moves) -> Vec<(Option<usize>, Option<usize>)> {
+    let mut old = ops
+        .iter()
+        .flat_map(|op| match op {
+            Op::Delete { old, .. } => old.clone(),
+            _ => 0..0,
+        })
+        .peekable();
+    let mut new = ops
+        .iter()
+        .flat_map(|op| match op {
+            Op::Insert { new, .. } => new.clone(),
+            _ => 0..0,
+        })
+        .peekable();
+
+    let mut pairs = Vec::new();
+    loop {
+        let pair = match (old.peek(), new.peek()) {
+            (Some(&i), _) if moves.of_old(i).is_some() => (old.next(), None),
+            (_, Some(&j)) if moves.of_new(j).is_some() => (None, new.next()),
+            (None, None) => break,
+            _ => (old.next(), new.next()),
+        };
+        pairs.push(pair);
+    }
+    pairs
+}
@@ -0,0 +1,133 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The colors used to colorize output, as SGR parameters like `1;32` for
+/// bold green.
+///
+/// Lines moved elsewhere in the file get their own colors, as in git's
+/// `--color-moved`, with neighbouring blocks alternating between two sets so
+/// they can be told apart.
+///
+/// Palettes can be parsed from the syntax of GNU diff's `--palette` option,
+/// a `:`-separated list of `key=value` pairs like `ad=1;32:de=1;31`. The
+/// keys are:
+///
+/// | key  | field            | default |
+/// |------|------------------|---------|
+/// | `rs` | `reset`          | `0`     |
+/// | `hd` | `header`         | `1`     |
+/// | `ln` | `hunk`           | `36`    |
+/// | `de` | `delete`         | `31`    |
+/// | `ad` | `insert`         | `32`    |
+/// | `mf` | `moved_from`     | `1;35`  |
+/// | `mt` | `moved_to`       | `1;36`  |
+/// | `af` | `moved_from_alt` | `1;34`  |
+/// | `at` | `moved_to_alt`   | `1;33`  |
+/// | `hl` | `highlight`      | `7`     |
+///
+/// Keys that aren't given keep their default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    /// Resets the colors at the end of each line.
+    pub reset: String,
+
+    /// File headers.
+    pub header: String,
+
+    /// Hunk headers, or the commands of normal diffs.
+    pub hunk: String,
+
+    /// Deleted lines.
+    pub delete: String,
+
+    /// Inserted lines.
+    pub insert: String,
+
+    /// Lines where they were moved from.
+    pub moved_from: String,
+
+    /// Lines where they were moved to.
+    pub moved_to: String,
+
+    /// Lines where they were moved from, for every other moved block.
+    pub moved_from_alt: String,
+
+    /// Lines where they were moved to, for every other moved block.
+    pub moved_to_alt: String,
+
+    /// The parts of changed lines that actually changed, on top of the
+    /// line's own color. Reverse video by default.
+    pub highlight: String,
+}
+
+impl Default for Palette {
+    /// The colors GNU diff uses, along with git's colors for moved lines.
+    fn default() -> Self {
+        Self {
+            reset: "0".into(),
+            header: "1".into(),
+            hunk: "36".into(),
+            delete: "31".into(),
+            insert: "32".into(),
+            moved_from: "1;35".into(),
+            moved_to: "1;36".into(),
+            moved_from_alt: "1;34".into(),
+            moved_to_alt: "1;33".into(),
+            highlight: "7".into(),
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = ParsePaletteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut palette = Self::default();
+        for entry in s.split(':').filter(|entry| !entry.is_empty()) {
+            let (key, value) = entry
+                .split_once('=')
+                .ok_or_else(|| ParsePaletteError::InvalidEntry(entry.into()))?;
+            if !value.bytes().all(|b| b.is_ascii_digit() || b == b';') {
+                return Err(ParsePaletteError::InvalidEntry(entry.into()));
+            }
+            let field = match key {
+                "rs" => &mut palette.reset,
+                "hd" => &mut palette.header,
+                "ln" => &mut palette.hunk,
+                "de" => &mut palette.delete,
+                "ad" => &mut palette.insert,
+                "mf" => &mut palette.moved_from,
+                "mt" => &mut palette.moved_to,
+                "af" => &mut palette.moved_from_alt,
+                "at" => &mut palette.moved_to_alt,
+                "hl" => &mut palette.highlight,
+                _ => return Err(ParsePaletteError::UnknownKey(key.into())),
+            };
+            *field = value.into();
+        }
+        Ok(palette)
+    }
+}
+
+/// An error parsing a [`Palette`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePaletteError {
+    /// An entry isn't of the form `key=value`, with a value made up of
+    /// digits and `;`.
+    InvalidEntry(String),
+
+    /// An entry's key isn't one of the known keys.
+    UnknownKey(String),
+}
+
+impl fmt::Display for ParsePaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEntry(entry) => write!(f, "invalid palette entry `{entry}`"),
+            Self::UnknownKey(key) => write!(f, "unknown palette key `{key}`"),
+        }
+    }
+}
+
+impl Error for ParsePaletteError {}
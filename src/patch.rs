@@ -0,0 +1,192 @@
+use crate::{Diff, Op};
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+/// A single operation in a patch. Unlike an [`Op`], this carries the lines
+/// the operation needs, so a patch can be applied without the new file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PatchOp<T> {
+    /// Copy lines `old` of the old file, which are expected to be `lines`.
+    Equal { old: Range<usize>, lines: Vec<T> },
+
+    /// Insert `lines`.
+    Insert { lines: Vec<T> },
+
+    /// Skip lines `old` of the old file, which are expected to be `lines`.
+    Delete { old: Range<usize>, lines: Vec<T> },
+
+    /// Copy lines `old` of the old file, which are expected to be `lines`,
+    /// from wherever they are in the old file.
+    Move { old: Range<usize>, lines: Vec<T> },
+}
+
+/// A self-contained edit script that can rebuild the new file from the old
+/// file alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Patch<T> {
+    old_len: usize,
+    ops: Vec<PatchOp<T>>,
+}
+
+impl<T: Clone> Patch<T> {
+    /// Build a patch from the edit script `diff` between `old` and `new`.
+    pub fn new(diff: &Diff, old: &[T], new: &[T]) -> Self {
+        let ops = diff
+            .ops()
+            .iter()
+            .map(|op| match op {
+                Op::Equal { old: lines, .. } => PatchOp::Equal {
+                    old: lines.clone(),
+                    lines: old[lines.clone()].to_vec(),
+                },
+                Op::Insert { new: lines, .. } => PatchOp::Insert {
+                    lines: new[lines.clone()].to_vec(),
+                },
+                Op::Delete { old: lines, .. } => PatchOp::Delete {
+                    old: lines.clone(),
+                    lines: old[lines.clone()].to_vec(),
+                },
+                Op::Move { old: lines, .. } => PatchOp::Move {
+                    old: lines.clone(),
+                    lines: old[lines.clone()].to_vec(),
+                },
+            })
+            .collect();
+        Self {
+            old_len: diff.old_len(),
+            ops,
+        }
+    }
+}
+
+impl<T> Patch<T> {
+    /// The number of lines the old file is expected to have.
+    pub fn old_len(&self) -> usize {
+        self.old_len
+    }
+
+    /// The operations making up this patch, in order.
+    pub fn ops(&self) -> &[PatchOp<T>] {
+        &self.ops
+    }
+
+    /// Convert the lines carried by this patch with `f`, e.g. to turn a patch
+    /// borrowing its lines into one that owns them.
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Patch<U> {
+        let mut lines = |lines: Vec<T>| lines.into_iter().map(&mut f).collect();
+        let ops = self
+            .ops
+            .into_iter()
+            .map(|op| match op {
+                PatchOp::Equal { old, lines: l } => PatchOp::Equal {
+                    old,
+                    lines: lines(l),
+                },
+                PatchOp::Insert { lines: l } => PatchOp::Insert { lines: lines(l) },
+                PatchOp::Delete { old, lines: l } => PatchOp::Delete {
+                    old,
+                    lines: lines(l),
+                },
+                PatchOp::Move { old, lines: l } => PatchOp::Move {
+                    old,
+                    lines: lines(l),
+                },
+            })
+            .collect();
+        Patch {
+            old_len: self.old_len,
+            ops,
+        }
+    }
+}
+
+/// An error applying a patch to a file it wasn't made for, or applying a
+/// malformed script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ApplyError {
+    /// The old file doesn't have the number of lines the patch expects.
+    LengthMismatch { expected: usize, actual: usize },
+
+    /// Line `line` (zero-based) of the old file isn't what the patch expects.
+    ContentMismatch { line: usize },
+
+    /// Line `line` (zero-based) of an ed or RCS script is malformed, or
+    /// refers to lines the old file doesn't have.
+    InvalidScript { line: usize },
+
+    /// An operation of a malformed patch refers to lines `range` (zero-based),
+    /// which the old file doesn't have.
+    OutOfBounds { range: Range<usize> },
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LengthMismatch { expected, actual } => {
+                write!(f, "expected {expected} lines, found {actual}")
+            }
+            Self::ContentMismatch { line } => {
+                write!(f, "line {} doesn't match the patch", line + 1)
+            }
+            Self::InvalidScript { line } => {
+                write!(f, "invalid command on line {} of the script", line + 1)
+            }
+            Self::OutOfBounds { range } => {
+                write!(
+                    f,
+                    "lines {}..{} are out of bounds",
+                    range.start + 1,
+                    range.end
+                )
+            }
+        }
+    }
+}
+
+impl Error for ApplyError {}
+
+/// Rebuild the new file by applying `patch` to `old`. Fails if `old` isn't
+/// the file the patch was made from.
+pub fn apply<T: Clone + PartialEq>(old: &[T], patch: &Patch<T>) -> Result<Vec<T>, ApplyError> {
+    if old.len() != patch.old_len {
+        return Err(ApplyError::LengthMismatch {
+            expected: patch.old_len,
+            actual: old.len(),
+        });
+    }
+
+    let mut new = Vec::new();
+    for op in &patch.ops {
+        match op {
+            PatchOp::Insert { lines } => new.extend_from_slice(lines),
+            PatchOp::Delete { old: range, lines } => verify(old, range, lines)?,
+            PatchOp::Equal { old: range, lines } | PatchOp::Move { old: range, lines } => {
+                verify(old, range, lines)?;
+                new.extend_from_slice(lines);
+            }
+        }
+    }
+    Ok(new)
+}
+
+/// Check that lines `range` of `old` are `expected`.
+fn verify<T: PartialEq>(old: &[T], range: &Range<usize>, expected: &[T]) -> Result<(), ApplyError> {
+    let actual = old
+        .get(range.clone())
+        .ok_or_else(|| ApplyError::OutOfBounds {
+            range: range.clone(),
+        })?;
+    match actual.iter().zip(expected).position(|(a, e)| a != e) {
+        Some(offset) => Err(ApplyError::ContentMismatch {
+            line: range.start + offset,
+        }),
+        None if actual.len() != expected.len() => Err(ApplyError::ContentMismatch {
+            line: range.start + actual.len(),
+        }),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,84 @@
+//! RCS (`diff -n`) output, along with an applier for it.
+//!
+//! Each change is a `dL N` command deleting `N` lines starting at line `L`
+//! and/or an `aL N` command appending the `N` lines that follow it after line
+//! `L`. Line numbers always refer to the old file, so lines are inserted
+//! verbatim and the format can represent any file.
+
+use crate::{ApplyError, Op, Text, TextDiff};
+use std::io::{self, Write};
+
+/// Write `diff` to `out` in RCS format. Nothing is written if the files are
+/// identical.
+pub fn write<W: Write>(out: &mut W, diff: &TextDiff) -> io::Result<()> {
+    let text = diff.new_text();
+    for hunk in diff.hunks(0) {
+        let (old, new) = (hunk.old_range(), hunk.new_range());
+        if !old.is_empty() {
+            writeln!(out, "d{} {}", old.start + 1, old.len())?;
+        }
+        if !new.is_empty() {
+            writeln!(out, "a{} {}", old.end, new.len())?;
+        }
+        for op in hunk.ops() {
+            if let Op::Insert { new, .. } = op {
+                for i in new.clone() {
+                    out.write_all(text.raw_line(i))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Render `diff` in RCS format. Lines are copied byte for byte, so the output
+/// is only valid UTF-8 if the files are.
+pub fn render(diff: &TextDiff) -> Vec<u8> {
+    let mut out = Vec::new();
+    write(&mut out, diff).expect("writing to a Vec never fails");
+    out
+}
+
+/// Apply the RCS script `script` to `old`, returning the new file.
+pub fn apply(old: &[u8], script: &[u8]) -> Result<Vec<u8>, ApplyError> {
+    let old = Text::new(old);
+    let script = Text::new(script);
+    let mut new: Vec<&[u8]> = Vec::with_capacity(old.len());
+
+    // the number of lines of the old file dealt with so far; commands have
+    // to be in order, so they can't go back before this
+    let mut done = 0;
+    let mut i = 0;
+    while i < script.len() {
+        let n = i;
+        let invalid = || ApplyError::InvalidScript { line: n };
+        let command = script.line(i);
+        i += 1;
+
+        let (&name, args) = command.split_first().ok_or_else(invalid)?;
+        let (line, count) = parse_args(args).ok_or_else(invalid)?;
+        match name {
+            b'd' if done < line && count > 0 && line - 1 + count <= old.len() => {
+                new.extend((done..line - 1).map(|i| old.raw_line(i)));
+                done = line - 1 + count;
+            }
+            b'a' if done <= line && line <= old.len() && i + count <= script.len() => {
+                new.extend((done..line).map(|i| old.raw_line(i)));
+                new.extend((i..i + count).map(|i| script.raw_line(i)));
+                done = line;
+                i += count;
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    new.extend((done..old.len()).map(|i| old.raw_line(i)));
+    Ok(new.concat())
+}
+
+/// Parse the arguments of a command, e.g. `5 3`, into its line number and
+/// line count.
+fn parse_args(args: &[u8]) -> Option<(usize, usize)> {
+    let args = std::str::from_utf8(args).ok()?;
+    let (line, count) = args.split_once(' ')?;
+    Some((line.parse().ok()?, count.parse().ok()?))
+}
@@ -0,0 +1,92 @@
+//! Intra-line refinement, for finding which parts of a changed line changed.
+//!
+//! Deleted lines are paired up with the inserted lines that replaced them,
+//! and each pair is diffed again, a word or character at a time, with the
+//! same algorithm used for lines. The result is the spans of each line that
+//! changed, for renderers to highlight, like `git diff --word-diff`.
+
+use crate::tokenize::{Chars, Graphemes, TokenDiff, Tokenizer, UnicodeWords};
+use crate::Op;
+use std::ops::Range;
+
+/// The units lines are split into when refining them, for choosing one of
+/// the built-in tokenizers at run time. Any other [`Tokenizer`] can be used
+/// to refine lines too.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Granularity {
+    /// Words, see [`UnicodeWords`].
+    #[default]
+    Word,
+
+    /// Single characters, see [`Chars`].
+    Char,
+
+    /// Extended grapheme clusters, see [`Graphemes`].
+    Grapheme,
+}
+
+impl Tokenizer for Granularity {
+    fn tokenize(&self, text: &[u8]) -> Vec<Range<usize>> {
+        match self {
+            Self::Word => UnicodeWords.tokenize(text),
+            Self::Char => Chars.tokenize(text),
+            Self::Grapheme => Graphemes.tokenize(text),
+        }
+    }
+}
+
+/// The spans of a pair of lines that changed, as sorted, non-overlapping
+/// byte ranges of each line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Refinement {
+    /// The changed spans of the old line.
+    pub old: Vec<Range<usize>>,
+
+    /// The changed spans of the new line.
+    pub new: Vec<Range<usize>>,
+}
+
+/// A deleted line paired with the inserted line that replaced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinePair {
+    /// The deleted line of the old file.
+    pub old: usize,
+
+    /// The inserted line of the new file.
+    pub new: usize,
+
+    /// The spans of each line that changed.
+    pub refinement: Refinement,
+}
+
+/// Find the spans of `old` and `new`, the contents of a pair of lines, that
+/// changed, diffing the tokens `tokenizer` splits them into. Anything not in
+/// a span is the same in both lines; in particular, tokens that were moved
+/// within the line are part of spans.
+pub fn refine<T: Tokenizer + ?Sized>(old: &[u8], new: &[u8], tokenizer: &T) -> Refinement {
+    let diff = TokenDiff::new(old, new, tokenizer);
+    let mut old_same = vec![false; diff.old_tokens().len()];
+    let mut new_same = vec![false; diff.new_tokens().len()];
+    for op in diff.diff().ops() {
+        if let Op::Equal { old, new } = op {
+            old_same[old.clone()].fill(true);
+            new_same[new.clone()].fill(true);
+        }
+    }
+    Refinement {
+        old: spans(diff.old_tokens(), &old_same),
+        new: spans(diff.new_tokens(), &new_same),
+    }
+}
+
+/// Merge the tokens that aren't the same into spans.
+fn spans(tokens: &[Range<usize>], same: &[bool]) -> Vec<Range<usize>> {
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    for (token, _) in tokens.iter().zip(same).filter(|(_, &same)| !same) {
+        match spans.last_mut() {
+            Some(span) if span.end == token.start => span.end = token.end,
+            _ => spans.push(token.clone()),
+        }
+    }
+    spans
+}
@@ -0,0 +1,327 @@
+//! Side-by-side (`diff -y`) output, showing the old and new file in two
+//! columns with a gutter between them describing each line:
+//!
+//! - ` `: the line is unchanged
+//! - `|`: the line was changed
+//! - `<`: the line was deleted
+//! - `>`: the line was inserted
+//! - `{`: the line was moved away from here
+//! - `}`: the line was moved here
+//!
+//! Like GNU diff, `(` marks unchanged lines when only the left column is
+//! shown for them, and `\` or `/` mark changed lines where only the old or
+//! new line is missing its newline.
+
+use crate::output::{pair_changes, Moves, Spans, Style};
+use crate::refine::Granularity;
+use crate::{Op, Palette, Text, TextDiff};
+use std::io::{self, Write};
+use std::ops::Range;
+
+/// The minimum width of the gutter between the columns.
+const GUTTER_WIDTH: usize = 3;
+
+/// Options for side-by-side output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// The total width of the output, in columns.
+    pub width: usize,
+
+    /// The distance between tab stops.
+    pub tab_size: usize,
+
+    /// Whether to expand tabs into spaces, rather than writing them as is.
+    pub expand_tabs: bool,
+
+    /// Only show the left column for unchanged lines.
+    pub left_column: bool,
+
+    /// Leave out unchanged lines entirely.
+    pub suppress_common_lines: bool,
+
+    /// The colors to colorize the output with, using ANSI escape sequences,
+    /// or `None` to leave it uncolored.
+    pub color: Option<Palette>,
+
+    /// Whether to highlight the parts of changed lines that changed, when
+    /// the output is colored, and how finely to split lines to find them.
+    pub refine: Option<Granularity>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            width: 130,
+            tab_size: 8,
+            expand_tabs: false,
+            left_column: false,
+            suppress_common_lines: false,
+            color: None,
+            refine: None,
+        }
+    }
+}
+
+/// Write `diff` to `out` in side-by-side format. Unlike the other formats,
+/// this writes the whole of both files, even if they're identical.
+pub fn write<W: Write>(out: &mut W, diff: &TextDiff, options: &Options) -> io::Result<()> {
+    let moves = Moves::new(diff.diff());
+    let spans = Spans::new(diff, options.refine.filter(|_| options.color.is_some()));
+    let mut writer = Writer::new(out, diff, options, &moves, &spans);
+
+    let mut reported = diff.hunks(0).into_iter().peekable();
+    let ops = diff.diff().flatten();
+    for group in
+        ops.chunk_by(|a, b| !matches!(a, Op::Equal { .. }) && !matches!(b, Op::Equal { .. }))
+    {
+        if let [Op::Equal { old, new }] = group {
+            if !options.suppress_common_lines {
+                for (i, j) in old.clone().zip(new.clone()) {
+                    writer.row(Some(i), ' ', Some(j))?;
+                }
+            }
+            continue;
+        }
+
+        // changes that are ignored are shown as though they were unchanged,
+        // like GNU diff does
+        let start = group[0].old_range().start;
+        if reported
+            .next_if(|hunk| hunk.old_range().start == start)
+            .is_none()
+        {
+            if !options.suppress_common_lines {
+                let old = group.iter().flat_map(|op| match op {
+                    Op::Delete { old, .. } => old.clone(),
+                    _ => 0..0,
+                });
+                let new = group.iter().flat_map(|op| match op {
+                    Op::Insert { new, .. } => new.clone(),
+                    _ => 0..0,
+                });
+                let (mut old, mut new) = (old.fuse(), new.fuse());
+                loop {
+                    match (old.next(), new.next()) {
+                        (None, None) => break,
+                        (i, j) => writer.row(i, ' ', j)?,
+                    }
+                }
+            }
+            continue;
+        }
+
+        // moved lines are shown on their own, and marked differently
+        for pair in pair_changes(group, &moves) {
+            match pair {
+                (Some(i), None) if moves.of_old(i).is_some() => writer.row(Some(i), '{', None)?,
+                (None, Some(j)) if moves.of_new(j).is_some() => writer.row(None, '}', Some(j))?,
+                (Some(i), Some(j)) => writer.row(Some(i), '|', Some(j))?,
+                (Some(i), None) => writer.row(Some(i), '<', None)?,
+                (old, new) => writer.row(old, '>', new)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Render `diff` in side-by-side format. Lines are copied byte for byte, so
+/// the output is only valid UTF-8 if the files are.
+pub fn render(diff: &TextDiff, options: &Options) -> Vec<u8> {
+    let mut out = Vec::new();
+    write(&mut out, diff, options).expect("writing to a Vec never fails");
+    out
+}
+
+/// Writes rows of the output, laid out the way GNU diff does.
+struct Writer<'a, W> {
+    out: &'a mut W,
+    old: &'a Text<'a>,
+    new: &'a Text<'a>,
+    options: &'a Options,
+    moves: &'a Moves,
+    spans: &'a Spans,
+
+    /// The width of each column.
+    half_width: usize,
+
+    /// The column the right column starts at.
+    right_offset: usize,
+}
+
+impl<'a, W: Write> Writer<'a, W> {
+    fn new(
+        out: &'a mut W,
+        diff: &'a TextDiff,
+        options: &'a Options,
+        moves: &'a Moves,
+        spans: &'a Spans,
+    ) -> Self {
+        // line the right column up with a tab stop, unless tabs are expanded
+        let stop = match options.expand_tabs {
+            true => 1,
+            false => options.tab_size,
+        };
+        let offset = (options.width + stop + GUTTER_WIDTH) / (2 * stop) * stop;
+        let half_width = offset
+            .saturating_sub(GUTTER_WIDTH)
+            .min(options.width.saturating_sub(offset));
+        Self {
+            out,
+            old: diff.old_text(),
+            new: diff.new_text(),
+            options,
+            moves,
+            spans,
+            half_width,
+            right_offset: if half_width > 0 {
+                offset
+            } else {
+                options.width
+            },
+        }
+    }
+
+    /// Write a row showing line `old` of the old file and line `new` of the
+    /// new file, either of which may be missing, separated by `gutter`.
+    fn row(&mut self, old: Option<usize>, gutter: char, new: Option<usize>) -> io::Result<()> {
+        let (gutter, new) = match (gutter, self.options.left_column) {
+            (' ', true) => ('(', None),
+            _ => (gutter, new),
+        };
+
+        let mut column = 0;
+        let mut newline = false;
+        let changed = !matches!(gutter, ' ' | '(');
+        if let Some(i) = old {
+            newline |= !self.old.is_missing_newline(i);
+            let (style, spans) = match changed {
+                true => (Style::deleted(self.moves, i), self.spans.of_old(i)),
+                false => (Style::Plain, &[][..]),
+            };
+            column = self.half_line(self.old.line(i), style, spans)?;
+        }
+        if gutter != ' ' {
+            column = self.pad(
+                column,
+                (self.half_width + self.right_offset).saturating_sub(1) / 2,
+            )? + 1;
+            let gutter = match new.map(|j| self.new.is_missing_newline(j)) {
+                Some(missing) if gutter == '|' && newline == missing => {
+                    if newline {
+                        '/'
+                    } else {
+                        '\\'
+                    }
+                }
+                _ => gutter,
+            };
+            write!(self.out, "{gutter}")?;
+        }
+        if let Some(j) = new {
+            newline |= !self.new.is_missing_newline(j);
+            if !self.new.line(j).is_empty() {
+                self.pad(column, self.right_offset)?;
+                let (style, spans) = match changed {
+                    true => (Style::inserted(self.moves, j), self.spans.of_new(j)),
+                    false => (Style::Plain, &[][..]),
+                };
+                self.half_line(self.new.line(j), style, spans)?;
+            }
+        }
+        if newline {
+            self.out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Write as much of `line` as fits in a column, expanding tabs relative
+    /// to the start of the column, and return its width. If the output is
+    /// colored, the line is colored according to `style`, with the byte
+    /// ranges `spans` highlighted.
+    fn half_line(
+        &mut self,
+        line: &[u8],
+        style: Style,
+        spans: &[Range<usize>],
+    ) -> io::Result<usize> {
+        let colors = self
+            .options
+            .color
+            .as_ref()
+            .and_then(|palette| Some((palette, style.sgr(palette)?)));
+        if let Some((_, sgr)) = colors {
+            write!(self.out, "\x1b[{sgr}m")?;
+        }
+
+        let (bound, tab_size) = (self.half_width, self.options.tab_size);
+        let (mut position, mut written) = (0, 0);
+        let (mut offset, mut highlighted) = (0, false);
+        for chunk in line.utf8_chunks() {
+            for c in chunk.valid().chars() {
+                if let Some((palette, sgr)) = colors.filter(|(p, _)| !p.highlight.is_empty()) {
+                    let highlight = spans.iter().any(|span| span.contains(&offset));
+                    match (highlighted, highlight) {
+                        (false, true) => write!(self.out, "\x1b[{}m", palette.highlight)?,
+                        (true, false) => write!(self.out, "\x1b[{}m\x1b[{sgr}m", palette.reset)?,
+                        _ => {}
+                    }
+                    highlighted = highlight;
+                }
+                offset += c.len_utf8();
+
+                let mut buf = [0; 4];
+                match c {
+                    '\t' => {
+                        let tab_stop = position + tab_size - position % tab_size;
+                        if position == written {
+                            if self.options.expand_tabs {
+                                let tab_stop = tab_stop.min(bound);
+                                write!(self.out, "{:1$}", "", tab_stop - written)?;
+                                written = tab_stop;
+                            } else if tab_stop < bound {
+                                self.out.write_all(b"\t")?;
+                                written = tab_stop;
+                            }
+                        }
+                        position = tab_stop;
+                    }
+                    // other control characters take up no space
+                    c if c.is_control() => {
+                        if position < bound {
+                            self.out.write_all(c.encode_utf8(&mut buf).as_bytes())?;
+                        }
+                    }
+                    c => {
+                        position += 1;
+                        if position <= bound {
+                            self.out.write_all(c.encode_utf8(&mut buf).as_bytes())?;
+                            written = position;
+                        }
+                    }
+                }
+            }
+            if position < bound {
+                self.out.write_all(chunk.invalid())?;
+            }
+            offset += chunk.invalid().len();
+        }
+        if let Some((palette, _)) = colors {
+            write!(self.out, "\x1b[{}m", palette.reset)?;
+        }
+        Ok(written)
+    }
+
+    /// Pad from column `from` to column `to`, with tabs where possible
+    /// unless tabs are expanded. Returns `to`.
+    fn pad(&mut self, mut from: usize, to: usize) -> io::Result<usize> {
+        let tab_size = self.options.tab_size;
+        if !self.options.expand_tabs {
+            while from + tab_size - from % tab_size <= to {
+                self.out.write_all(b"\t")?;
+                from += tab_size - from % tab_size;
+            }
+        }
+        write!(self.out, "{:1$}", "", to.saturating_sub(from))?;
+        Ok(to)
+    }
+}
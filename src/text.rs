@@ -0,0 +1,396 @@
+use crate::algorithm::DiffAlgorithm;
+use crate::cleanup::{clean_up, Cleanup};
+use crate::output::{pair_changes, Moves};
+use crate::refine::{refine, LinePair};
+use crate::tokenize::Tokenizer;
+use crate::{Diff, DiffOptions, Heckel, Hunk, Normalizer, Op, Patch};
+use std::borrow::Cow;
+
+/// The number of bytes checked for NUL bytes when deciding whether a file is
+/// binary, like git does.
+const BINARY_PROBE_LEN: usize = 8000;
+
+/// The terminator at the end of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+
+    /// `\r\n`
+    CrLf,
+
+    /// No terminator, which is only possible on the last line of a file.
+    Missing,
+}
+
+impl LineEnding {
+    /// The bytes making up this terminator.
+    pub fn as_bytes(self) -> &'static [u8] {
+        match self {
+            Self::Lf => b"\n",
+            Self::CrLf => b"\r\n",
+            Self::Missing => b"",
+        }
+    }
+
+    /// Split `line` into its contents and its terminator.
+    pub fn split(line: &[u8]) -> (&[u8], Self) {
+        if let Some(content) = line.strip_suffix(b"\r\n") {
+            (content, Self::CrLf)
+        } else if let Some(content) = line.strip_suffix(b"\n") {
+            (content, Self::Lf)
+        } else {
+            (line, Self::Missing)
+        }
+    }
+}
+
+/// The lines of a text file. The text is either borrowed or owned, and lines
+/// are stored as offsets into it, so splitting never copies the text. Text is
+/// treated as raw bytes, so it doesn't need to be valid UTF-8.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Text<'a> {
+    text: Cow<'a, [u8]>,
+
+    /// The offset each line starts at, followed by the length of the text.
+    starts: Vec<usize>,
+}
+
+impl<'a> Text<'a> {
+    /// Split `text` into lines. Both `\n` and `\r\n` are treated as line
+    /// terminators; each line remembers which one it ended with.
+    pub fn new(text: impl Into<Cow<'a, [u8]>>) -> Self {
+        let text = text.into();
+        let mut starts = vec![0];
+        starts.extend(
+            text.iter()
+                .enumerate()
+                .filter_map(|(i, &b)| (b == b'\n').then_some(i + 1)),
+        );
+        if starts.last() != Some(&text.len()) {
+            starts.push(text.len());
+        }
+        Self { text, starts }
+    }
+
+    /// The whole text.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.text
+    }
+
+    /// The lines of the file, without their terminators.
+    pub fn lines(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        (0..self.len()).map(|i| self.line(i))
+    }
+
+    /// The lines of the file, including their terminators.
+    pub fn raw_lines(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        (0..self.len()).map(|i| self.raw_line(i))
+    }
+
+    /// Line `i` of the file, without its terminator.
+    pub fn line(&self, i: usize) -> &[u8] {
+        LineEnding::split(self.raw_line(i)).0
+    }
+
+    /// Line `i` of the file, including its terminator.
+    pub fn raw_line(&self, i: usize) -> &[u8] {
+        &self.text[self.starts[i]..self.starts[i + 1]]
+    }
+
+    /// The terminator at the end of line `i`.
+    pub fn line_ending(&self, i: usize) -> LineEnding {
+        LineEnding::split(self.raw_line(i)).1
+    }
+
+    /// Line `i` of the file, without its terminator, for display. Invalid
+    /// UTF-8 is replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn line_lossy(&self, i: usize) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.line(i))
+    }
+
+    /// The number of lines in the file.
+    pub fn len(&self) -> usize {
+        self.starts.len() - 1
+    }
+
+    /// Whether the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether line `i` is the last line of the file and is missing its
+    /// terminating newline.
+    pub fn is_missing_newline(&self, i: usize) -> bool {
+        self.line_ending(i) == LineEnding::Missing
+    }
+
+    /// Whether the file looks like a binary file rather than text, i.e. it
+    /// contains a NUL byte near the start.
+    pub fn is_binary(&self) -> bool {
+        self.text[..self.text.len().min(BINARY_PROBE_LEN)].contains(&0)
+    }
+
+    /// The symbols to diff: each line, including its terminator (so lines
+    /// that only differ in how they end don't match), as normalized by
+    /// `normalizer`.
+    fn symbols<N: Normalizer + ?Sized>(&self, normalizer: &N) -> Vec<Cow<'_, [u8]>> {
+        self.raw_lines()
+            .map(|line| normalizer.normalize(line))
+            .collect()
+    }
+
+    /// Which lines `normalizer` allows changes to be ignored for. This is
+    /// left empty if there are none, to save allocating for the common case.
+    fn ignorable<N: Normalizer + ?Sized>(&self, normalizer: &N) -> Vec<bool> {
+        let ignorable: Vec<bool> = self
+            .raw_lines()
+            .map(|line| normalizer.is_ignorable(line))
+            .collect();
+        match ignorable.contains(&true) {
+            true => ignorable,
+            false => Vec::new(),
+        }
+    }
+}
+
+/// The result of diffing two text files: the edit script, along with the
+/// lines it refers to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "TextDiffParts<'a>")
+)]
+pub struct TextDiff<'a> {
+    old: Text<'a>,
+    new: Text<'a>,
+    diff: Diff,
+
+    /// Lines of the old file that can be left out of changes, if any.
+    old_ignorable: Vec<bool>,
+
+    /// Lines of the new file that can be left out of changes, if any.
+    new_ignorable: Vec<bool>,
+}
+
+impl<'a> TextDiff<'a> {
+    /// Diff the lines of `old` against the lines of `new`.
+    pub fn new(old: Text<'a>, new: Text<'a>) -> Self {
+        Self::with_options(old, new, &DiffOptions::default())
+    }
+
+    /// Diff the lines of `old` against the lines of `new`, comparing lines
+    /// according to `options`.
+    pub fn with_options(old: Text<'a>, new: Text<'a>, options: &DiffOptions) -> Self {
+        Self::with_normalizer(old, new, options)
+    }
+
+    /// Diff the lines of `old` against the lines of `new`, comparing lines
+    /// by the keys `normalizer` maps them to.
+    pub fn with_normalizer<N: Normalizer + ?Sized>(
+        old: Text<'a>,
+        new: Text<'a>,
+        normalizer: &N,
+    ) -> Self {
+        Self::with_algorithm(old, new, normalizer, &Heckel::default())
+    }
+
+    /// Diff the lines of `old` against the lines of `new`, comparing lines
+    /// by the keys `normalizer` maps them to, with `algorithm` rather than
+    /// Heckel's algorithm with its default options.
+    pub fn with_algorithm<N: Normalizer + ?Sized, A: DiffAlgorithm>(
+        old: Text<'a>,
+        new: Text<'a>,
+        normalizer: &N,
+        algorithm: &A,
+    ) -> Self {
+        let diff = algorithm.diff(&old.symbols(normalizer), &new.symbols(normalizer));
+        Self {
+            old_ignorable: old.ignorable(normalizer),
+            new_ignorable: new.ignorable(normalizer),
+            old,
+            new,
+            diff,
+        }
+    }
+
+    /// The old file.
+    pub fn old_text(&self) -> &Text<'a> {
+        &self.old
+    }
+
+    /// The new file.
+    pub fn new_text(&self) -> &Text<'a> {
+        &self.new
+    }
+
+    /// The edit script transforming the old file into the new file.
+    pub fn diff(&self) -> &Diff {
+        &self.diff
+    }
+
+    /// Group the changes into hunks, each surrounded by up to `context`
+    /// unchanged lines (see [`Diff::hunks`]). Hunks consisting only of
+    /// changes to ignorable lines are left out.
+    pub fn hunks(&self, context: usize) -> Vec<Hunk> {
+        let ignorable = |lines: &[bool], range: std::ops::Range<usize>| {
+            !lines.is_empty() && range.into_iter().all(|i| lines[i])
+        };
+        let mut hunks = self.diff.hunks(context);
+        hunks.retain(|hunk| {
+            !hunk.ops().iter().all(|op| match op {
+                Op::Equal { .. } => true,
+                Op::Delete { old, .. } => ignorable(&self.old_ignorable, old.clone()),
+                Op::Insert { new, .. } => ignorable(&self.new_ignorable, new.clone()),
+                Op::Move { .. } => false,
+            })
+        });
+        hunks
+    }
+
+    /// Whether the files are identical, apart from changes to ignorable
+    /// lines.
+    pub fn is_identical(&self) -> bool {
+        self.hunks(0).is_empty()
+    }
+
+    /// Pair up the deleted and inserted lines of each run of changes, in
+    /// order, and find the spans of each pair that changed, split into
+    /// tokens by `tokenizer` (see [`refine`](crate::refine::refine)). Lines
+    /// that were moved, and lines left over once the shorter side runs out,
+    /// aren't paired. Pairs are in the order of both files.
+    pub fn refine<T: Tokenizer + ?Sized>(&self, tokenizer: &T) -> Vec<LinePair> {
+        let moves = Moves::new(&self.diff);
+        self.diff
+            .flatten()
+            .chunk_by(|a, b| !matches!(a, Op::Equal { .. }) && !matches!(b, Op::Equal { .. }))
+            .filter(|group| !matches!(group, [Op::Equal { .. }]))
+            .flat_map(|group| pair_changes(group, &moves))
+            .filter_map(|pair| match pair {
+                (Some(old), Some(new)) => Some(LinePair {
+                    old,
+                    new,
+                    refinement: refine(self.old.line(old), self.new.line(new), tokenizer),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Post-process the edit script according to `cleanup` (see
+    /// [`clean_up`](crate::cleanup::clean_up)). Lines are compared byte for
+    /// byte, including their terminators.
+    pub fn clean_up(&mut self, cleanup: &Cleanup) {
+        let old: Vec<&[u8]> = self.old.raw_lines().collect();
+        let new: Vec<&[u8]> = self.new.raw_lines().collect();
+        self.diff = clean_up(&self.diff, &old, &new, cleanup);
+    }
+
+    /// A patch that rebuilds the lines of the new file from the old file.
+    /// Lines include their terminators, so concatenating the lines returned
+    /// by [`apply`](crate::apply) reproduces the new file byte for byte.
+    pub fn patch(&self) -> Patch<Vec<u8>> {
+        let old: Vec<&[u8]> = self.old.raw_lines().collect();
+        let new: Vec<&[u8]> = self.new.raw_lines().collect();
+        Patch::new(&self.diff, &old, &new).map(<[u8]>::to_vec)
+    }
+}
+
+/// A deserialized [`TextDiff`], before it's been checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct TextDiffParts<'a> {
+    old: Text<'a>,
+    new: Text<'a>,
+    diff: Diff,
+    old_ignorable: Vec<bool>,
+    new_ignorable: Vec<bool>,
+}
+
+#[cfg(feature = "serde")]
+impl<'a> TryFrom<TextDiffParts<'a>> for TextDiff<'a> {
+    type Error = String;
+
+    /// Check that the edit script and the ignorable lines fit the texts.
+    fn try_from(parts: TextDiffParts<'a>) -> Result<Self, String> {
+        let TextDiffParts {
+            old,
+            new,
+            diff,
+            old_ignorable,
+            new_ignorable,
+        } = parts;
+        for (side, text, len, ignorable) in [
+            ("old", &old, diff.old_len(), &old_ignorable),
+            ("new", &new, diff.new_len(), &new_ignorable),
+        ] {
+            if len != text.len() {
+                return Err(format!(
+                    "the diff expects {len} lines in the {side} file, found {}",
+                    text.len()
+                ));
+            }
+            if !ignorable.is_empty() && ignorable.len() != text.len() {
+                return Err(format!(
+                    "{} ignorable lines given for the {side} file, which has {}",
+                    ignorable.len(),
+                    text.len()
+                ));
+            }
+        }
+        Ok(Self {
+            old,
+            new,
+            diff,
+            old_ignorable,
+            new_ignorable,
+        })
+    }
+}
+
+/// Text is serialized as its bytes, and split into lines again when it's
+/// deserialized.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Text<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.text)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Text<'_> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a byte array")
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+                Ok(bytes.to_vec())
+            }
+
+            fn visit_byte_buf<E: serde::de::Error>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+                Ok(bytes)
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<Vec<u8>, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(b) = seq.next_element()? {
+                    bytes.push(b);
+                }
+                Ok(bytes)
+            }
+        }
+
+        deserializer.deserialize_byte_buf(Visitor).map(Text::new)
+    }
+}
@@ -0,0 +1,229 @@
+//! Splitting text into tokens other than lines, for diffing prose a word or
+//! character at a time.
+//!
+//! A [`Tokenizer`] splits text into tokens, and [`TokenDiff`] diffs the
+//! tokens of two texts with the same passes used for lines, keeping track of
+//! where each token came from. Diffing two paragraphs a word at a time:
+//!
+//! ```
+//! use heckel_diff::tokenize::{TokenDiff, UnicodeWords};
+//! use heckel_diff::Op;
+//!
+//! let diff = TokenDiff::new(b"The quick fox.", b"The slow fox.", &UnicodeWords);
+//! let inserted: Vec<_> = diff
+//!     .diff()
+//!     .ops()
+//!     .iter()
+//!     .filter_map(|op| match op {
+//!         Op::Insert { new, .. } => Some(diff.new_span(new.clone())),
+//!         _ => None,
+//!     })
+//!     .collect();
+//! assert_eq!(inserted, [4..8]);
+//! ```
+
+use crate::{diff_slices, Diff};
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Splits text into tokens to diff.
+pub trait Tokenizer {
+    /// Split `text` into tokens, returned as byte ranges of `text`. Tokens
+    /// are non-empty and in order, and the built-in tokenizers cover all of
+    /// `text`, so concatenating the tokens gives back the text.
+    fn tokenize(&self, text: &[u8]) -> Vec<Range<usize>>;
+}
+
+/// Lines, including their terminators. These are the same lines
+/// [`Text`](crate::Text) splits files into.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Lines;
+
+impl Tokenizer for Lines {
+    fn tokenize(&self, text: &[u8]) -> Vec<Range<usize>> {
+        let mut tokens = Vec::new();
+        let mut start = 0;
+        for (i, _) in text.iter().enumerate().filter(|(_, &b)| b == b'\n') {
+            tokens.push(start..i + 1);
+            start = i + 1;
+        }
+        if start < text.len() {
+            tokens.push(start..text.len());
+        }
+        tokens
+    }
+}
+
+/// Runs of non-white space characters, and the runs of white space between
+/// them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Words;
+
+impl Tokenizer for Words {
+    fn tokenize(&self, text: &[u8]) -> Vec<Range<usize>> {
+        runs(text, |b| b.is_ascii_whitespace())
+    }
+}
+
+/// Words as defined by Unicode's word boundary rules: runs of letters and
+/// digits, runs of white space, and single punctuation characters. Bytes
+/// that aren't valid UTF-8 are a token each.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnicodeWords;
+
+impl Tokenizer for UnicodeWords {
+    fn tokenize(&self, text: &[u8]) -> Vec<Range<usize>> {
+        segments(text, |s| {
+            s.split_word_bound_indices()
+                .map(|(i, word)| i..i + word.len())
+                .collect()
+        })
+    }
+}
+
+/// Single characters. Bytes that aren't valid UTF-8 are a token each.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Chars;
+
+impl Tokenizer for Chars {
+    fn tokenize(&self, text: &[u8]) -> Vec<Range<usize>> {
+        segments(text, |s| {
+            s.char_indices().map(|(i, c)| i..i + c.len_utf8()).collect()
+        })
+    }
+}
+
+/// Extended grapheme clusters, what readers think of as single characters,
+/// such as a letter along with its accents or an emoji sequence. Bytes that
+/// aren't valid UTF-8 are a token each.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Graphemes;
+
+impl Tokenizer for Graphemes {
+    fn tokenize(&self, text: &[u8]) -> Vec<Range<usize>> {
+        segments(text, |s| {
+            s.grapheme_indices(true)
+                .map(|(i, grapheme)| i..i + grapheme.len())
+                .collect()
+        })
+    }
+}
+
+/// Split `text` into maximal runs of bytes that either all match `split`
+/// or all don't.
+fn runs(text: &[u8], split: impl Fn(u8) -> bool) -> Vec<Range<usize>> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    for end in 1..=text.len() {
+        if text
+            .get(end)
+            .is_none_or(|&b| split(b) != split(text[end - 1]))
+        {
+            tokens.push(start..end);
+            start = end;
+        }
+    }
+    tokens
+}
+
+/// Split the valid UTF-8 parts of `text` with `segment`, making each invalid
+/// byte a token of its own.
+fn segments(text: &[u8], segment: impl Fn(&str) -> Vec<Range<usize>>) -> Vec<Range<usize>> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    for chunk in text.utf8_chunks() {
+        let valid = chunk.valid();
+        tokens.extend(
+            segment(valid)
+                .into_iter()
+                .map(|token| start + token.start..start + token.end),
+        );
+        start += valid.len();
+        for _ in chunk.invalid() {
+            tokens.push(start..start + 1);
+            start += 1;
+        }
+    }
+    tokens
+}
+
+/// The result of diffing the tokens of two texts: the edit script, along
+/// with where each token is in its text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenDiff<'a> {
+    old: &'a [u8],
+    new: &'a [u8],
+    old_tokens: Vec<Range<usize>>,
+    new_tokens: Vec<Range<usize>>,
+    diff: Diff,
+}
+
+impl<'a> TokenDiff<'a> {
+    /// Diff the tokens of `old` against the tokens of `new`, split by
+    /// `tokenizer`. Tokens are compared byte for byte.
+    pub fn new<T: Tokenizer + ?Sized>(old: &'a [u8], new: &'a [u8], tokenizer: &T) -> Self {
+        let old_tokens = tokenizer.tokenize(old);
+        let new_tokens = tokenizer.tokenize(new);
+        let slices = |text: &'a [u8], tokens: &[Range<usize>]| -> Vec<&'a [u8]> {
+            tokens.iter().map(|token| &text[token.clone()]).collect()
+        };
+        let diff = diff_slices(&slices(old, &old_tokens), &slices(new, &new_tokens));
+        Self {
+            old,
+            new,
+            old_tokens,
+            new_tokens,
+            diff,
+        }
+    }
+
+    /// The edit script transforming the old tokens into the new tokens. Its
+    /// ranges are ranges of tokens, see [`old_span`](Self::old_span) and
+    /// [`new_span`](Self::new_span) for the bytes they cover.
+    pub fn diff(&self) -> &Diff {
+        &self.diff
+    }
+
+    /// The byte ranges of the tokens of the old text.
+    pub fn old_tokens(&self) -> &[Range<usize>] {
+        &self.old_tokens
+    }
+
+    /// The byte ranges of the tokens of the new text.
+    pub fn new_tokens(&self) -> &[Range<usize>] {
+        &self.new_tokens
+    }
+
+    /// Token `i` of the old text.
+    pub fn old_token(&self, i: usize) -> &'a [u8] {
+        &self.old[self.old_tokens[i].clone()]
+    }
+
+    /// Token `j` of the new text.
+    pub fn new_token(&self, j: usize) -> &'a [u8] {
+        &self.new[self.new_tokens[j].clone()]
+    }
+
+    /// The byte range of the old text covered by the range of tokens
+    /// `tokens`. An empty range of tokens gives an empty range at the start
+    /// of the next token, or the end of the text.
+    pub fn old_span(&self, tokens: Range<usize>) -> Range<usize> {
+        span(&self.old_tokens, self.old.len(), tokens)
+    }
+
+    /// The byte range of the new text covered by the range of tokens
+    /// `tokens`, like [`old_span`](Self::old_span).
+    pub fn new_span(&self, tokens: Range<usize>) -> Range<usize> {
+        span(&self.new_tokens, self.new.len(), tokens)
+    }
+}
+
+/// The byte range covered by the range of tokens `range`, of a text `len`
+/// bytes long split into `tokens`.
+fn span(tokens: &[Range<usize>], len: usize, range: Range<usize>) -> Range<usize> {
+    let start = tokens.get(range.start).map_or(len, |token| token.start);
+    match range.is_empty() {
+        true => start..start,
+        false => start..tokens[range.end - 1].end,
+    }
+}
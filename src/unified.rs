@@ -0,0 +1,166 @@
+//! Unified (`diff -u`) output.
+
+use crate::output::{write_line, write_refined_line, Moves, Spans, Style, Timestamp};
+use crate::refine::Granularity;
+use crate::{LineEnding, Op, Palette, TextDiff};
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::time::SystemTime;
+
+/// The header naming one of the files being diffed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Header {
+    /// The file's label, usually its path.
+    pub label: String,
+
+    /// When the file was last modified, if known.
+    pub modified: Option<SystemTime>,
+}
+
+impl Header {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            modified: None,
+        }
+    }
+
+    pub fn modified(mut self, modified: SystemTime) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+
+    /// Format the header with its timestamp in either the unified or the
+    /// traditional style.
+    pub(crate) fn write(&self, f: &mut fmt::Formatter<'_>, traditional: bool) -> fmt::Result {
+        f.write_str(&self.label)?;
+        if let Some(time) = self.modified {
+            write!(f, "\t{}", Timestamp { time, traditional })?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, false)
+    }
+}
+
+/// Options for unified output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// The number of unchanged lines to show around each change.
+    pub context: usize,
+
+    /// The header for the old file.
+    pub old: Header,
+
+    /// The header for the new file.
+    pub new: Header,
+
+    /// The colors to colorize the output with, using ANSI escape sequences,
+    /// or `None` to leave it uncolored.
+    pub color: Option<Palette>,
+
+    /// Whether to highlight the parts of changed lines that changed, when
+    /// the output is colored, and how finely to split lines to find them.
+    pub refine: Option<Granularity>,
+}
+
+impl Options {
+    pub fn new(old: Header, new: Header) -> Self {
+        Self {
+            context: 3,
+            old,
+            new,
+            color: None,
+            refine: None,
+        }
+    }
+}
+
+/// Write `diff` to `out` in unified format. Nothing is written if the files
+/// are identical.
+pub fn write<W: Write>(out: &mut W, diff: &TextDiff, options: &Options) -> io::Result<()> {
+    let hunks = diff.hunks(options.context);
+    if hunks.is_empty() {
+        return Ok(());
+    }
+
+    let palette = options.color.as_ref();
+    let moves = Moves::new(diff.diff());
+    let spans = Spans::new(diff, options.refine.filter(|_| palette.is_some()));
+    write_line(
+        out,
+        palette,
+        Style::Header,
+        b"--- ",
+        options.old.to_string().as_bytes(),
+        LineEnding::Lf,
+    )?;
+    write_line(
+        out,
+        palette,
+        Style::Header,
+        b"+++ ",
+        options.new.to_string().as_bytes(),
+        LineEnding::Lf,
+    )?;
+    for hunk in hunks {
+        let header = format!(
+            "@@ -{} +{} @@",
+            HunkRange(hunk.old_range()),
+            HunkRange(hunk.new_range())
+        );
+        write_line(
+            out,
+            palette,
+            Style::Hunk,
+            b"",
+            header.as_bytes(),
+            LineEnding::Lf,
+        )?;
+        for op in hunk.ops() {
+            let (prefix, text, lines) = match op {
+                Op::Equal { old, .. } => (b" ", diff.old_text(), old.clone()),
+                Op::Delete { old, .. } => (b"-", diff.old_text(), old.clone()),
+                Op::Insert { new, .. } => (b"+", diff.new_text(), new.clone()),
+                Op::Move { .. } => unreachable!("hunks never contain moves"),
+            };
+            for i in lines {
+                let (style, spans) = match op {
+                    Op::Delete { .. } => (Style::deleted(&moves, i), spans.of_old(i)),
+                    Op::Insert { .. } => (Style::inserted(&moves, i), spans.of_new(i)),
+                    _ => (Style::Plain, &[][..]),
+                };
+                let (line, ending) = (text.line(i), text.line_ending(i));
+                write_refined_line(out, palette, style, prefix, line, spans, ending)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Render `diff` in unified format. Lines are copied byte for byte, so the
+/// output is only valid UTF-8 if the files are.
+pub fn render(diff: &TextDiff, options: &Options) -> Vec<u8> {
+    let mut out = Vec::new();
+    write(&mut out, diff, options).expect("writing to a Vec never fails");
+    out
+}
+
+/// A range of lines in a hunk header. Empty ranges are identified by the
+/// line before them, and the length is left off ranges of a single line.
+pub(crate) struct HunkRange(pub Range<usize>);
+
+impl fmt::Display for HunkRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.len() {
+            0 => write!(f, "{},0", self.0.start),
+            1 => write!(f, "{}", self.0.start + 1),
+            len => write!(f, "{},{len}", self.0.start + 1),
+        }
+    }
+}
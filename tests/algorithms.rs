@@ -0,0 +1,80 @@
+use heckel_diff::algorithm::{Algorithm, DiffAlgorithm, Histogram, Myers, Patience};
+use heckel_diff::{apply, diff_str, Heckel, Op, Patch, Text, TextDiff};
+
+mod common;
+
+use common::{for_each_case, for_each_pair, lcs_len, unchanged};
+
+const ALGORITHMS: [Algorithm; 4] = [
+    Algorithm::Heckel(Heckel {
+        fill_gaps: false,
+        anchor_depth: 0,
+    }),
+    Algorithm::Myers,
+    Algorithm::Patience,
+    Algorithm::Histogram,
+];
+
+#[test]
+fn every_algorithm_rebuilds_the_new_file() {
+    // each algorithm gets its own cases, so between them they cover more
+    let seeds = [
+        0x510e527fade682d1,
+        0x9b05688c2b3e6c1f,
+        0x1f83d9abfb41bd6b,
+        0x5be0cd19137e2179,
+    ];
+    for (algorithm, seed) in ALGORITHMS.into_iter().zip(seeds) {
+        for_each_case(seed, 1000, |old, new| {
+            let diff = algorithm.diff(old, new);
+            let patch = Patch::new(&diff, old, new);
+            assert_eq!(apply(old, &patch), Ok(new.to_vec()), "{algorithm:?}");
+        });
+    }
+}
+
+#[test]
+fn myers_finds_a_longest_common_subsequence() {
+    for_each_pair(0xcbbb9d5dc1059ed8, 500, 40, 5, |old, new| {
+        assert_eq!(unchanged(&Myers.diff(old, new)), lcs_len(old, new));
+    });
+}
+
+#[test]
+fn patience_and_histogram_anchor_on_rare_lines() {
+    let old = ["A", "}", "}", "}", "B"];
+    let new = ["B", "}", "}", "}", "A"];
+
+    assert_eq!(unchanged(&Myers.diff(&old, &new)), 3);
+    assert_eq!(unchanged(&Patience.diff(&old, &new)), 1);
+    assert_eq!(unchanged(&Histogram.diff(&old, &new)), 1);
+}
+
+#[test]
+fn text_diffs_take_an_algorithm() {
+    let (old, new) = ("a\nb\nc\n", "c\na\nb\n");
+    let heckel = diff_str(old, new);
+    let myers = TextDiff::with_algorithm(
+        Text::new(old.as_bytes()),
+        Text::new(new.as_bytes()),
+        &heckel_diff::DiffOptions::default(),
+        &Myers,
+    );
+
+    assert!(heckel
+        .diff()
+        .ops()
+        .iter()
+        .any(|op| matches!(op, Op::Move { .. })));
+    assert_eq!(
+        myers.diff().ops(),
+        [
+            Op::Insert { old: 0, new: 0..1 },
+            Op::Equal {
+                old: 0..2,
+                new: 1..3
+            },
+            Op::Delete { old: 2..3, new: 3 },
+        ]
+    );
+}
@@ -0,0 +1,36 @@
+use heckel_diff::html;
+use heckel_diff::unified::{self, Header};
+use heckel_diff::{diff_bytes, Text};
+
+#[test]
+fn files_with_a_nul_byte_near_the_start_are_binary() {
+    assert!(Text::new(&b"\x7fELF\0\x01"[..]).is_binary());
+    assert!(Text::new(&b"a\nb\0\n"[..]).is_binary());
+    assert!(!Text::new(&b""[..]).is_binary());
+    assert!(!Text::new(&b"caf\xe9\n"[..]).is_binary());
+
+    // only the start of the file is checked, like GNU diff
+    let mut late = vec![b'a'; 10_000];
+    late.push(0);
+    assert!(!Text::new(late).is_binary());
+}
+
+#[test]
+fn invalid_utf8_is_diffed_and_printed_byte_for_byte() {
+    // "café" in Latin-1 and in UTF-8
+    let (old, new) = (&b"caf\xe9\nsame\n"[..], &b"caf\xc3\xa9\nsame\n"[..]);
+    let diff = diff_bytes(old, new);
+    assert!(!diff.is_identical());
+
+    let options = unified::Options::new(Header::new("old.txt"), Header::new("new.txt"));
+    assert_eq!(
+        unified::render(&diff, &options),
+        b"--- old.txt\n+++ new.txt\n@@ -1,2 +1,2 @@\n-caf\xe9\n+caf\xc3\xa9\n same\n"
+    );
+
+    // HTML has to be valid UTF-8, so the invalid byte is replaced
+    let options = html::Options::new(Header::new("old.txt"), Header::new("new.txt"));
+    let output = html::render(&diff, &options);
+    assert!(output.contains("caf\u{fffd}"));
+    assert!(output.contains("café"));
+}
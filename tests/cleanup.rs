@@ -0,0 +1,87 @@
+use heckel_diff::cleanup::{clean_up, Cleanup};
+use heckel_diff::{apply, diff_slices, diff_str, Op, Patch};
+
+mod common;
+
+use common::{for_each_case, SYMBOLS};
+
+const SLIDE: Cleanup = Cleanup {
+    slide: true,
+    merge_equalities: 0,
+};
+
+#[test]
+fn blocks_slide_to_line_up_with_indentation() {
+    let old = "fn f() {\n    x();\n        z();\nfn f() {\n        z();\n\n    if a {\n}\n";
+    let new = "fn f() {\n        z();\n\n    if a {\n}\n";
+    let mut diff = diff_str(old, new);
+    assert_eq!(
+        diff.diff().ops()[..2],
+        [
+            Op::Equal {
+                old: 0..1,
+                new: 0..1
+            },
+            Op::Delete { old: 1..4, new: 1 },
+        ]
+    );
+
+    // the deleted lines are a whole function, as git would show them
+    diff.clean_up(&SLIDE);
+    assert_eq!(
+        diff.diff().ops(),
+        [
+            Op::Delete { old: 0..3, new: 0 },
+            Op::Equal {
+                old: 3..8,
+                new: 0..5
+            },
+        ]
+    );
+}
+
+#[test]
+fn short_equalities_between_changes_are_merged() {
+    let old = ["a", "x", "b", "y", "c"];
+    let new = ["A", "x", "B", "y", "C"];
+    let diff = diff_slices(&old, &new);
+    let cleanup = Cleanup {
+        merge_equalities: 1,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        clean_up(&diff, &old, &new, &cleanup).ops(),
+        [
+            Op::Delete { old: 0..5, new: 0 },
+            Op::Insert { old: 5, new: 0..5 },
+        ]
+    );
+    // the unchanged lines aren't shorter than the changes around them
+    let old = ["a", "x", "y", "b"];
+    let new = ["A", "x", "y", "B"];
+    let diff = diff_slices(&old, &new);
+    assert_eq!(clean_up(&diff, &old, &new, &cleanup), diff);
+}
+
+#[test]
+fn cleaned_up_diffs_still_rebuild_the_new_file() {
+    // lines that give the indent heuristic something to work with
+    let lines: [&str; SYMBOLS] = [
+        "{", "}", "", "    x();", "\ty();", "    }", "z();", "  w();", "if a {", "",
+    ];
+    let cleanup = Cleanup {
+        slide: true,
+        merge_equalities: 2,
+    };
+    for_each_case(0x428a2f98d728ae22, 2000, |old, new| {
+        let old: Vec<&str> = old.iter().map(|&s| lines[s as usize]).collect();
+        let new: Vec<&str> = new.iter().map(|&s| lines[s as usize]).collect();
+
+        let diff = diff_slices(&old, &new);
+        let cleaned = clean_up(&diff, &old, &new, &cleanup);
+        assert_eq!(cleaned.moves().count(), diff.moves().count());
+        let patch = Patch::new(&cleaned, &old, &new);
+        assert_eq!(apply(&old, &patch), Ok(new), "old: {old:?}");
+    });
+}
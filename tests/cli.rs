@@ -0,0 +1,72 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+/// Run the binary with `args`, feeding it `stdin`.
+fn run(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_heckel-diff"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn standard_input_can_be_compared_with_itself() {
+    let output = run(&["-", "-"], b"a\nb\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn exit_codes_match_gnu_diff() {
+    let identical = run(&["assets/left.txt", "assets/left.txt"], b"");
+    assert_eq!(identical.status.code(), Some(0));
+    assert!(identical.stdout.is_empty());
+
+    let different = run(&["assets/left.txt", "assets/right.txt"], b"");
+    assert_eq!(different.status.code(), Some(1));
+    assert!(!different.stdout.is_empty());
+
+    let missing = run(&["assets/left.txt", "assets/missing.txt"], b"");
+    assert_eq!(missing.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&missing.stderr);
+    assert!(
+        stderr.starts_with("heckel-diff: failed to read assets/missing.txt: "),
+        "{stderr}"
+    );
+
+    let usage = run(&["-W", "0", "assets/left.txt", "assets/right.txt"], b"");
+    assert_eq!(usage.status.code(), Some(2));
+}
+
+#[test]
+fn binary_files_are_only_reported_as_differing() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let (old, new) = (dir.join("old.bin"), dir.join("new.bin"));
+    fs::write(&old, b"\0\x01\x02\nsame\n").unwrap();
+    fs::write(&new, b"\0\x01\x03\nsame\n").unwrap();
+    let (old, new) = (old.to_str().unwrap(), new.to_str().unwrap());
+
+    let output = run(&[old, new], b"");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("Binary files {old} and {new} differ\n")
+    );
+
+    let output = run(&[old, old], b"");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+
+    // -a diffs them as text anyway
+    let output = run(&["-a", "--normal", old, new], b"");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, b"1c1\n< \0\x01\x02\n---\n> \0\x01\x03\n");
+}
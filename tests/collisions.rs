@@ -0,0 +1,40 @@
+use heckel_diff::{diff_slices, diff_slices_with_hasher, Op};
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// A hasher that maps every value to the same hash.
+#[derive(Default)]
+struct CollidingHasher;
+
+impl Hasher for CollidingHasher {
+    fn finish(&self) -> u64 {
+        0
+    }
+
+    fn write(&mut self, _bytes: &[u8]) {}
+}
+
+#[test]
+fn colliding_lines_stay_distinct() {
+    let old = ["a", "b", "c", "d"];
+    let new = ["a", "x", "c", "d", "y"];
+
+    let diff =
+        diff_slices_with_hasher(&old, &new, BuildHasherDefault::<CollidingHasher>::default());
+    assert_eq!(diff, diff_slices(&old, &new));
+    assert_eq!(
+        diff.ops(),
+        [
+            Op::Equal {
+                old: 0..1,
+                new: 0..1
+            },
+            Op::Delete { old: 1..2, new: 1 },
+            Op::Insert { old: 2, new: 1..2 },
+            Op::Equal {
+                old: 2..4,
+                new: 2..4
+            },
+            Op::Insert { old: 4, new: 4..5 },
+        ]
+    );
+}
@@ -0,0 +1,46 @@
+use heckel_diff::{diff_str, normal, Palette, ParsePaletteError};
+
+#[test]
+fn moved_lines_alternate_colors() {
+    let diff = diff_str("a\nb\nc\nd\ne\nf\n", "a\nd\nb\nc\nx\nf\ne\n");
+    let options = normal::Options {
+        color: Some(Palette::default()),
+        ..Default::default()
+    };
+    let output = normal::render(&diff, &options);
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        "\x1b[36m1a2\x1b[0m\n\x1b[1;36m> d\x1b[0m\n\
+         \x1b[36m4,5c5\x1b[0m\n\x1b[1;35m< d\x1b[0m\n\x1b[1;34m< e\x1b[0m\n---\n\x1b[32m> x\x1b[0m\n\
+         \x1b[36m6a7\x1b[0m\n\x1b[1;33m> e\x1b[0m\n"
+    );
+}
+
+#[test]
+fn palettes_use_gnu_syntax() {
+    let palette: Palette = "ad=1;32:de=:mf=2".parse().unwrap();
+    assert_eq!(palette.insert, "1;32");
+    assert_eq!(palette.delete, "");
+    assert_eq!(palette.moved_from, "2");
+    assert_eq!(palette.hunk, Palette::default().hunk);
+
+    let diff = diff_str("a\n", "b\n");
+    let options = normal::Options {
+        color: Some(palette),
+        ..Default::default()
+    };
+    let output = normal::render(&diff, &options);
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        "\x1b[36m1c1\x1b[0m\n< a\n---\n\x1b[1;32m> b\x1b[0m\n"
+    );
+
+    assert_eq!(
+        "ad=32:xx=1".parse::<Palette>(),
+        Err(ParsePaletteError::UnknownKey("xx".into()))
+    );
+    assert_eq!(
+        "ad=green".parse::<Palette>(),
+        Err(ParsePaletteError::InvalidEntry("ad=green".into()))
+    );
+}
@@ -0,0 +1,114 @@
+//! Helpers shared by the integration tests. Each test crate only uses some
+//! of them.
+#![allow(dead_code)]
+
+use heckel_diff::{Diff, Op};
+use std::fs;
+use std::path::Path;
+
+/// The contents of `name` in assets/.
+pub fn asset(name: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join(name);
+    fs::read(&path).unwrap_or_else(|err| panic!("failed to read {}: {err}", path.display()))
+}
+
+/// A small xorshift generator, so the tests are deterministic without
+/// pulling in a dependency.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+/// The number of distinct lines in the files [`for_each_case`] generates.
+pub const SYMBOLS: usize = 10;
+
+/// Make a random edit of `old`: deleting, inserting and moving lines, with
+/// inserted lines below [`SYMBOLS`].
+pub fn mutate(rng: &mut Rng, old: &[u8]) -> Vec<u8> {
+    let mut new = old.to_vec();
+    for _ in 0..rng.below(8) {
+        match rng.below(3) {
+            0 if !new.is_empty() => {
+                new.remove(rng.below(new.len()));
+            }
+            1 => new.insert(rng.below(new.len() + 1), rng.below(SYMBOLS) as u8),
+            _ if new.len() > 4 => {
+                let at = rng.below(new.len() - 3);
+                let block: Vec<_> = new.drain(at..at + 3).collect();
+                let to = rng.below(new.len() + 1);
+                new.splice(to..to, block);
+            }
+            _ => {}
+        }
+    }
+    new
+}
+
+/// Call `f` with `n` random files of up to 50 lines, each line a symbol
+/// below [`SYMBOLS`], and a random edit of each made by [`mutate`].
+pub fn for_each_case(seed: u64, n: usize, mut f: impl FnMut(&[u8], &[u8])) {
+    let mut rng = Rng(seed);
+    for _ in 0..n {
+        let old: Vec<u8> = (0..rng.below(50))
+            .map(|_| rng.below(SYMBOLS) as u8)
+            .collect();
+        let new = mutate(&mut rng, &old);
+        f(&old, &new);
+    }
+}
+
+/// Call `f` with `n` random pairs of unrelated files of up to `len` lines,
+/// each line a symbol below `symbols`.
+pub fn for_each_pair(
+    seed: u64,
+    n: usize,
+    len: usize,
+    symbols: usize,
+    mut f: impl FnMut(&[u8], &[u8]),
+) {
+    let mut rng = Rng(seed);
+    let file = |rng: &mut Rng| -> Vec<u8> {
+        (0..rng.below(len + 1))
+            .map(|_| rng.below(symbols) as u8)
+            .collect()
+    };
+    for _ in 0..n {
+        let (old, new) = (file(&mut rng), file(&mut rng));
+        f(&old, &new);
+    }
+}
+
+/// The number of lines `diff` keeps unchanged.
+pub fn unchanged(diff: &Diff) -> usize {
+    diff.ops()
+        .iter()
+        .filter(|op| matches!(op, Op::Equal { .. }))
+        .map(Op::len)
+        .sum()
+}
+
+/// The length of a longest common subsequence of `a` and `b`.
+pub fn lcs_len(a: &[u8], b: &[u8]) -> usize {
+    let mut row = vec![0; b.len() + 1];
+    for x in a {
+        let mut diagonal = 0;
+        for (j, y) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if x == y {
+                diagonal + 1
+            } else {
+                above.max(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
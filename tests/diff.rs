@@ -0,0 +1,126 @@
+use heckel_diff::{diff_slices, Op};
+
+const OLD: [&str; 9] = ["a", "b", "c", "d", "e", "f", "g", "h", "i"];
+const NEW: [&str; 9] = ["b", "c", "X", "d", "e", "f", "g", "h", "a"];
+
+#[test]
+fn edit_scripts_report_moves_once() {
+    let diff = diff_slices(&OLD, &NEW);
+    assert_eq!(
+        diff.ops(),
+        [
+            Op::Equal {
+                old: 1..3,
+                new: 0..2
+            },
+            Op::Insert { old: 3, new: 2..3 },
+            Op::Equal {
+                old: 3..8,
+                new: 3..8
+            },
+            Op::Delete { old: 8..9, new: 8 },
+            Op::Move {
+                old: 0..1,
+                new: 8..9
+            },
+        ]
+    );
+    assert!(!diff.is_identical());
+    assert!(diff_slices(&OLD, &OLD).is_identical());
+}
+
+#[test]
+fn flattening_splits_moves_into_deletions_and_insertions() {
+    let diff = diff_slices(&OLD, &NEW);
+    assert_eq!(
+        diff.flatten(),
+        [
+            Op::Delete { old: 0..1, new: 0 },
+            Op::Equal {
+                old: 1..3,
+                new: 0..2
+            },
+            Op::Insert { old: 3, new: 2..3 },
+            Op::Equal {
+                old: 3..8,
+                new: 3..8
+            },
+            Op::Delete { old: 8..9, new: 8 },
+            Op::Insert { old: 9, new: 8..9 },
+        ]
+    );
+}
+
+#[test]
+fn hunks_share_short_runs_of_context() {
+    // the two unchanged lines between the first deletion and the insertion
+    // fit in one line of context on each side, so they share a hunk
+    let diff = diff_slices(&OLD, &NEW);
+    let hunks = diff.hunks(1);
+    assert_eq!(hunks.len(), 2);
+
+    assert_eq!((hunks[0].old_range(), hunks[0].new_range()), (0..4, 0..4));
+    assert_eq!(
+        hunks[0].ops(),
+        [
+            Op::Delete { old: 0..1, new: 0 },
+            Op::Equal {
+                old: 1..3,
+                new: 0..2
+            },
+            Op::Insert { old: 3, new: 2..3 },
+            Op::Equal {
+                old: 3..4,
+                new: 3..4
+            },
+        ]
+    );
+
+    assert_eq!((hunks[1].old_range(), hunks[1].new_range()), (7..9, 7..9));
+    assert_eq!(
+        hunks[1].ops(),
+        [
+            Op::Equal {
+                old: 7..8,
+                new: 7..8
+            },
+            Op::Delete { old: 8..9, new: 8 },
+            Op::Insert { old: 9, new: 8..9 },
+        ]
+    );
+
+    // the five unchanged lines in the middle only fit in three lines of
+    // context on each side
+    assert_eq!(diff.hunks(2).len(), 2);
+    assert_eq!(diff.hunks(3).len(), 1);
+}
+
+#[test]
+fn any_hashable_type_can_be_diffed() {
+    // the same edit script as for the strings, whatever the element type
+    let expected = diff_slices(&OLD, &NEW);
+    let index = |line: &&str| OLD.iter().chain(&NEW).position(|l| l == line).unwrap();
+
+    let old: Vec<usize> = OLD.iter().map(index).collect();
+    let new: Vec<usize> = NEW.iter().map(index).collect();
+    assert_eq!(diff_slices(&old, &new), expected);
+
+    let old: Vec<char> = OLD.iter().map(|l| l.chars().next().unwrap()).collect();
+    let new: Vec<char> = NEW.iter().map(|l| l.chars().next().unwrap()).collect();
+    assert_eq!(diff_slices(&old, &new), expected);
+
+    #[derive(PartialEq, Eq, Hash)]
+    struct Token<'a> {
+        text: &'a str,
+        kind: u8,
+    }
+    let token = |text| Token { text, kind: 0 };
+    let old: Vec<Token> = OLD.into_iter().map(token).collect();
+    let new: Vec<Token> = NEW.into_iter().map(token).collect();
+    assert_eq!(diff_slices(&old, &new), expected);
+
+    // values that compare unequal aren't matched, even if they look alike
+    let mut new = new;
+    new[0].kind = 1;
+    assert_ne!(diff_slices(&old, &new), expected);
+}
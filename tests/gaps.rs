@@ -0,0 +1,134 @@
+use heckel_diff::{apply, diff_slices, Heckel, Patch};
+
+mod common;
+
+use common::{for_each_case, for_each_pair, lcs_len, unchanged};
+
+const FILL_GAPS: Heckel = Heckel {
+    fill_gaps: true,
+    anchor_depth: 0,
+};
+
+#[test]
+fn repeated_lines_are_matched_minimally() {
+    let old = ["a", "}", "", "}", "b", "", "}", "c"];
+    let new = ["d", "", "}", "", "}", "e"];
+
+    // no line is unique, so nothing anchors the lines in between
+    assert_eq!(unchanged(&diff_slices(&old, &new)), 0);
+    assert_eq!(unchanged(&FILL_GAPS.diff(&old, &new)), 4);
+}
+
+#[test]
+fn lines_unique_within_a_gap_anchor_it() {
+    let old = ["f", "a", "return", "a", "g", "a", "return", "a"];
+    let new = ["f", "b", "return", "b", "g", "b", "return", "b"];
+    let anchor = |anchor_depth| Heckel {
+        anchor_depth,
+        ..Default::default()
+    };
+
+    assert_eq!(unchanged(&anchor(0).diff(&old, &new)), 2);
+    assert_eq!(unchanged(&anchor(1).diff(&old, &new)), 4);
+}
+
+#[test]
+fn anchoring_is_limited_in_depth() {
+    // `x` is unique within the gap between `U` and `V`, and splits it into
+    // two gaps with a `y` each
+    let old = ["U", "a", "y", "a", "x", "a", "y", "a", "V", "x"];
+    let new = ["U", "b", "y", "b", "x", "b", "y", "b", "V", "x"];
+    let unchanged_at = |anchor_depth| {
+        let heckel = Heckel {
+            anchor_depth,
+            ..Default::default()
+        };
+        unchanged(&heckel.diff(&old, &new))
+    };
+
+    assert_eq!(unchanged_at(0), 3);
+    assert_eq!(unchanged_at(1), 4);
+    assert_eq!(unchanged_at(2), 6);
+    assert_eq!(unchanged_at(3), 6);
+}
+
+#[test]
+fn gaps_without_anchors_get_a_longest_common_subsequence() {
+    let mut checked = 0;
+    for_each_pair(0x6a09e667f3bcc908, 2000, 30, 4, |old, new| {
+        let count = |lines: &[u8], line| lines.iter().filter(|&&l| l == line).count();
+        if (0..4).any(|line| count(old, line) == 1 && count(new, line) == 1) {
+            return;
+        }
+
+        let diff = FILL_GAPS.diff(old, new);
+        assert_eq!(unchanged(&diff), lcs_len(old, new), "{old:?} {new:?}");
+        let patch = Patch::new(&diff, old, new);
+        assert_eq!(apply(old, &patch), Ok(new.to_vec()));
+        checked += 1;
+    });
+    assert!(checked >= 500, "only {checked} cases without anchors");
+}
+
+#[test]
+fn filled_and_anchored_gaps_still_rebuild_the_new_file() {
+    for_each_case(0xbb67ae8584caa73b, 2000, |old, new| {
+        for heckel in [
+            FILL_GAPS,
+            Heckel {
+                fill_gaps: false,
+                anchor_depth: 4,
+            },
+            Heckel {
+                fill_gaps: true,
+                anchor_depth: 4,
+            },
+        ] {
+            let diff = heckel.diff(old, new);
+            assert!(unchanged(&diff) >= unchanged(&diff_slices(old, new)));
+            let patch = Patch::new(&diff, old, new);
+            assert_eq!(apply(old, &patch), Ok(new.to_vec()), "old: {old:?}");
+        }
+    });
+}
+
+#[test]
+fn anchoring_shrinks_diffs_of_real_code() {
+    // rewrite one line in seven of some of this crate's own source files, and
+    // add blocks made of the lines that repeat most in code
+    let (mut plain, mut anchored) = (0, 0);
+    for source in [
+        include_str!("../src/lib.rs"),
+        include_str!("../src/html.rs"),
+        include_str!("../src/side_by_side.rs"),
+        include_str!("../src/unified.rs"),
+    ] {
+        let old: Vec<&str> = source.lines().collect();
+        let mut new = Vec::new();
+        for (i, &line) in old.iter().enumerate() {
+            if i % 37 == 0 {
+                new.extend(["        }", "", "    }", ""]);
+            }
+            new.push(if i % 7 == 0 {
+                "        // changed"
+            } else {
+                line
+            });
+        }
+
+        let changed_at = |anchor_depth| {
+            let heckel = Heckel {
+                anchor_depth,
+                ..Default::default()
+            };
+            old.len() + new.len() - 2 * unchanged(&heckel.diff(&old, &new))
+        };
+        assert!(changed_at(8) <= changed_at(0));
+        plain += changed_at(0);
+        anchored += changed_at(8);
+    }
+    assert!(
+        anchored < plain,
+        "{anchored} changed lines, {plain} without anchoring"
+    );
+}
@@ -0,0 +1,164 @@
+//! Compare output against GNU diff's on the fixtures in assets/. The expected
+//! output was generated with GNU diffutils 3.8, e.g.
+//! `diff -c -L left.txt -L right.txt left.txt right.txt > left-right.context`
+//! or `diff -w messy.txt tidy.txt > messy-tidy.normal-ignore-all-space`.
+
+use heckel_diff::unified::Header;
+use heckel_diff::{context, ed, normal, rcs, side_by_side, unified, DiffOptions, Text, TextDiff};
+
+mod common;
+
+use common::asset;
+
+const FIXTURES: [(&str, &str); 2] = [("left", "right"), ("before", "after")];
+
+/// Check that `render` produces the same output for each of `fixtures` as
+/// GNU diff did, as saved in the fixture's file with the extension `format`.
+fn check(
+    format: &str,
+    fixtures: &[(&str, &str)],
+    render: impl Fn(&TextDiff, Header, Header) -> Vec<u8>,
+) {
+    check_with(format, fixtures, &DiffOptions::default(), render);
+}
+
+/// Like [`check`], but compares lines according to `options`.
+fn check_with(
+    format: &str,
+    fixtures: &[(&str, &str)],
+    options: &DiffOptions,
+    render: impl Fn(&TextDiff, Header, Header) -> Vec<u8>,
+) {
+    for &(old, new) in fixtures {
+        let (old_label, new_label) = (format!("{old}.txt"), format!("{new}.txt"));
+        let (old_text, new_text) = (asset(&old_label), asset(&new_label));
+        let diff = TextDiff::with_options(Text::new(old_text), Text::new(new_text), options);
+        let actual = render(&diff, Header::new(old_label), Header::new(new_label));
+        let expected = asset(&format!("{old}-{new}.{format}"));
+        assert_eq!(
+            String::from_utf8_lossy(&actual),
+            String::from_utf8_lossy(&expected),
+            "{format} output for {old} and {new}"
+        );
+    }
+}
+
+#[test]
+fn normal_output_matches_gnu_diff() {
+    check("normal", &FIXTURES, |diff, _, _| {
+        normal::render(diff, &normal::Options::default())
+    });
+}
+
+#[test]
+fn unified_output_matches_gnu_diff() {
+    check("unified", &FIXTURES, |diff, old, new| {
+        unified::render(diff, &unified::Options::new(old, new))
+    });
+
+    // without context, hunks of pure insertions and deletions have empty
+    // ranges, whose start is the line before them
+    check("unified-0", &FIXTURES, |diff, old, new| {
+        let mut options = unified::Options::new(old, new);
+        options.context = 0;
+        unified::render(diff, &options)
+    });
+}
+
+#[test]
+fn context_output_matches_gnu_diff() {
+    check("context", &FIXTURES, |diff, old, new| {
+        context::render(diff, &context::Options::new(old, new))
+    });
+}
+
+#[test]
+fn ed_output_matches_gnu_diff() {
+    // before.txt is missing its final newline, which ed scripts can't
+    // represent, so GNU diff fails on it too
+    check("ed", &[("left", "right")], |diff, _, _| {
+        ed::render(diff).unwrap()
+    });
+
+    let diff = TextDiff::new(
+        Text::new(asset("before.txt")),
+        Text::new(asset("after.txt")),
+    );
+    assert!(ed::render(&diff).is_err());
+}
+
+#[test]
+fn rcs_output_matches_gnu_diff() {
+    check("rcs", &FIXTURES, |diff, _, _| rcs::render(diff));
+}
+
+#[test]
+fn side_by_side_output_matches_gnu_diff() {
+    // GNU diff has no notion of moves, so only compare files without any
+    let options = side_by_side::Options {
+        width: 60,
+        ..Default::default()
+    };
+    check("side-by-side", &[("before", "after")], |diff, _, _| {
+        side_by_side::render(diff, &options)
+    });
+}
+
+#[test]
+fn comparison_options_match_gnu_diff() {
+    // messy.txt has a mix of line endings and no newline at the end, which
+    // the white space options ignore along with the white space itself
+    let fixtures = [("messy", "tidy")];
+    let render = |diff: &TextDiff, _, _| normal::render(diff, &normal::Options::default());
+    check("normal", &fixtures, render);
+
+    let cases = [
+        (
+            "ignore-all-space",
+            DiffOptions {
+                ignore_all_space: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "ignore-space-change",
+            DiffOptions {
+                ignore_space_change: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "ignore-trailing-space",
+            DiffOptions {
+                ignore_trailing_space: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "ignore-case",
+            DiffOptions {
+                ignore_case: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "ignore-blank-lines",
+            DiffOptions {
+                ignore_blank_lines: true,
+                ..Default::default()
+            },
+        ),
+        // GNU diff also strips the carriage returns it prints, so the lines
+        // ending in one only change their line ending
+        (
+            "strip-trailing-cr",
+            DiffOptions {
+                strip_trailing_cr: true,
+                ..Default::default()
+            },
+        ),
+    ];
+    for (name, options) in cases {
+        check_with(&format!("normal-{name}"), &fixtures, &options, render);
+    }
+}
@@ -0,0 +1,31 @@
+use heckel_diff::diff_str;
+use heckel_diff::html::{self, Options};
+use heckel_diff::unified::Header;
+
+fn render(old: &str, new: &str) -> String {
+    let options = Options::new(Header::new("old.txt"), Header::new("new.txt"));
+    html::render(&diff_str(old, new), &options)
+}
+
+#[test]
+fn moved_blocks_match_at_both_ends() {
+    let output = render("a\nb\nc\nd\ne\n", "a\nd\ne\nb\nc\nx\n");
+    assert!(output.contains("<td class=\"move move-0\" title=\"Moved to line 4\">b</td>"));
+    assert!(output.contains("<td class=\"move move-0\" title=\"Moved to line 5\">c</td>"));
+    assert!(output.contains("<td class=\"move move-0\" title=\"Moved from line 2\">b</td>"));
+    assert!(output.contains("<td class=\"move move-0\" title=\"Moved from line 3\">c</td>"));
+    assert!(output.contains("<td class=\"insert\">x</td>"));
+}
+
+#[test]
+fn changed_lines_are_highlighted_and_escaped() {
+    let output = render("keep\n<a href=\"x\">\n", "keep\n<a href=\"y\">\n");
+    assert!(output.contains(
+        "<td class=\"delete\">&lt;a href=&quot;<span class=\"hl\">x</span>&quot;&gt;</td>"
+    ));
+    assert!(output.contains(
+        "<td class=\"insert\">&lt;a href=&quot;<span class=\"hl\">y</span>&quot;&gt;</td>"
+    ));
+    assert!(output.contains("id=\"side-hunk-1\""));
+    assert!(output.contains("<a href=\"#inline-hunk-1\">@@ -1,2 +1,2 @@</a>"));
+}
@@ -0,0 +1,61 @@
+use heckel_diff::{diff_slices, Op};
+
+mod common;
+
+use common::asset;
+
+fn lines(name: &str) -> Vec<Vec<u8>> {
+    asset(name)
+        .split_inclusive(|&b| b == b'\n')
+        .map(<[u8]>::to_vec)
+        .collect()
+}
+
+#[test]
+fn moved_blocks_are_reported_where_they_land() {
+    // "LIKE" and "SNOW ," move up ahead of "A MASS OF", rather than being
+    // deleted and inserted again, and "SOFT" between them is deleted
+    let diff = diff_slices(&lines("left.txt"), &lines("right.txt"));
+    assert_eq!(
+        diff.ops(),
+        [
+            Op::Insert { old: 0, new: 0..3 },
+            Op::Move {
+                old: 10..11,
+                new: 3..4
+            },
+            Op::Move {
+                old: 12..14,
+                new: 4..6
+            },
+            Op::Equal {
+                old: 0..3,
+                new: 6..9
+            },
+            Op::Delete { old: 3..4, new: 9 },
+            Op::Insert { old: 4, new: 9..10 },
+            Op::Equal {
+                old: 4..5,
+                new: 10..11
+            },
+            Op::Insert {
+                old: 5,
+                new: 11..13
+            },
+            Op::Equal {
+                old: 5..10,
+                new: 13..18
+            },
+            Op::Delete {
+                old: 11..12,
+                new: 18
+            },
+            Op::Equal {
+                old: 14..19,
+                new: 18..23
+            },
+        ]
+    );
+    assert_eq!(diff.moves().count(), 2);
+    assert_eq!((diff.old_len(), diff.new_len()), (19, 23));
+}
@@ -0,0 +1,60 @@
+use heckel_diff::{normal, DiffOptions, Text, TextDiff};
+use regex::bytes::Regex;
+
+fn diff<'a>(old: &'a str, new: &'a str, options: &DiffOptions) -> TextDiff<'a> {
+    TextDiff::with_options(
+        Text::new(old.as_bytes()),
+        Text::new(new.as_bytes()),
+        options,
+    )
+}
+
+fn render(diff: &TextDiff) -> String {
+    String::from_utf8(normal::render(diff, &normal::Options::default())).unwrap()
+}
+
+#[test]
+fn ignored_lines_only_suppress_hunks_of_their_own() {
+    // the same output as `diff -I '^#'`: the first hunk only changes
+    // comments, but the second also changes a line that isn't one
+    let options = DiffOptions {
+        ignore_matching_lines: vec![Regex::new("^#").unwrap()],
+        ..Default::default()
+    };
+    let old = "a\n# 1\nb\nc\nd\ne\n";
+    let new = "a\n# 2\nb\nx\n# 3\nd\ne\n# 4\n";
+    let diff = diff(old, new, &options);
+    assert!(!diff.is_identical());
+    assert_eq!(render(&diff), "4c4,5\n< c\n---\n> x\n> # 3\n");
+
+    let diff = self::diff("a\n# 1\n", "a\n# 2\n", &options);
+    assert!(diff.is_identical());
+    assert_eq!(render(&diff), "");
+}
+
+#[test]
+fn masked_spans_are_ignored() {
+    let options = DiffOptions {
+        mask: vec![Regex::new(r"\d{2}:\d{2}").unwrap()],
+        ..Default::default()
+    };
+    let diff = diff(
+        "12:00 start\n12:01 stop\n",
+        "13:30 start\n13:31 halt\n",
+        &options,
+    );
+    // the original lines are still what's printed
+    assert_eq!(render(&diff), "2c2\n< 12:01 stop\n---\n> 13:31 halt\n");
+}
+
+#[test]
+fn masked_spans_dont_match_nul_bytes() {
+    let options = DiffOptions {
+        mask: vec![Regex::new(r"\d+").unwrap()],
+        ..Default::default()
+    };
+    assert!(diff("t=1\n", "t=23\n", &options).is_identical());
+    for nuls in ["t=\0\n", "t=\0\0\n", "t=\0\x01\n"] {
+        assert!(!diff("t=1\n", nuls, &options).is_identical(), "{nuls:?}");
+    }
+}
@@ -0,0 +1,62 @@
+use heckel_diff::refine::{refine, Granularity, LinePair, Refinement};
+use heckel_diff::unified::{self, Header};
+use heckel_diff::{diff_str, Palette};
+
+#[test]
+fn words_that_changed_are_found() {
+    let refinement = refine(
+        b"let x = foo(1, 2);",
+        b"let y = foo(1, 3);",
+        &Granularity::Word,
+    );
+    assert_eq!(
+        refinement,
+        Refinement {
+            old: vec![4..5, 15..16],
+            new: vec![4..5, 15..16],
+        }
+    );
+
+    // adjacent changed words make up a single span
+    let refinement = refine(b"a quick fox", b"a slow brown fox", &Granularity::Word);
+    assert_eq!(refinement.old, vec![2..7]);
+    assert_eq!(refinement.new, vec![2..12]);
+}
+
+#[test]
+fn characters_that_changed_are_found() {
+    let refinement = refine("héllo".as_bytes(), b"hello", &Granularity::Char);
+    assert_eq!(refinement.old, vec![1..3]);
+    assert_eq!(refinement.new, vec![1..2]);
+
+    let refinement = refine("héllo".as_bytes(), b"hello", &Granularity::Word);
+    assert_eq!(refinement.old, vec![0..6]);
+    assert_eq!(refinement.new, vec![0..5]);
+}
+
+#[test]
+fn changed_lines_are_paired_in_order() {
+    let diff = diff_str("a\nb c\nd\ne\nf g\n", "a\nb x\nd\ne\nf h\ny\n");
+    let pairs = diff.refine(&Granularity::Word);
+    let lines: Vec<_> = pairs.iter().map(|pair| (pair.old, pair.new)).collect();
+    assert_eq!(lines, [(1, 1), (4, 4)]);
+    assert_eq!(
+        pairs[1],
+        LinePair {
+            old: 4,
+            new: 4,
+            refinement: refine(b"f g", b"f h", &Granularity::Word),
+        }
+    );
+}
+
+#[test]
+fn refined_spans_are_highlighted_in_colored_output() {
+    let diff = diff_str("one two\n", "one three\n");
+    let mut options = unified::Options::new(Header::new("old"), Header::new("new"));
+    options.color = Some(Palette::default());
+    options.refine = Some(Granularity::Word);
+    let output = unified::render(&diff, &options);
+    assert!(String::from_utf8_lossy(&output)
+        .ends_with("\x1b[31m-one \x1b[7mtwo\x1b[0m\n\x1b[32m+one \x1b[7mthree\x1b[0m\n"));
+}
@@ -0,0 +1,120 @@
+use heckel_diff::{
+    apply, diff_bytes, diff_slices, diff_str, ed, rcs, ApplyError, Patch, PatchOp, Text,
+};
+
+mod common;
+
+use common::{for_each_case, Rng};
+
+#[test]
+fn patches_rebuild_the_new_file() {
+    for_each_case(0x2545f4914f6cdd1d, 2000, |old, new| {
+        let patch = Patch::new(&diff_slices(old, new), old, new);
+        assert_eq!(apply(old, &patch), Ok(new.to_vec()), "old: {old:?}");
+    });
+}
+
+#[test]
+fn patches_reject_the_wrong_base() {
+    let old = ["a", "b", "c", "d"];
+    let new = ["a", "c", "d", "b"];
+    let patch = Patch::new(&diff_slices(&old, &new), &old, &new);
+
+    assert_eq!(
+        apply(&["a", "b", "c"], &patch),
+        Err(ApplyError::LengthMismatch {
+            expected: 4,
+            actual: 3
+        })
+    );
+    assert_eq!(
+        apply(&["a", "x", "c", "d"], &patch),
+        Err(ApplyError::ContentMismatch { line: 1 })
+    );
+}
+
+#[test]
+fn patches_check_unchanged_lines_too() {
+    let old = ["a", "b", "c", "d"];
+    let new = ["a", "c", "d", "b"];
+    let patch = Patch::new(&diff_slices(&old, &new), &old, &new);
+    assert!(patch
+        .ops()
+        .iter()
+        .any(|op| matches!(op, PatchOp::Equal { old, .. } if old.contains(&2))));
+
+    assert_eq!(
+        apply(&["a", "b", "x", "d"], &patch),
+        Err(ApplyError::ContentMismatch { line: 2 })
+    );
+}
+
+#[test]
+fn text_patches_preserve_line_endings() {
+    let old = "a\r\nb\nc\r\nd";
+    let new = "a\nb\nc\r\nd\n";
+    let patch = diff_str(old, new).patch();
+    assert!(patch
+        .ops()
+        .iter()
+        .any(|op| matches!(op, PatchOp::Delete { .. })));
+
+    let old: Vec<Vec<u8>> = Text::new(old.as_bytes())
+        .raw_lines()
+        .map(<[u8]>::to_vec)
+        .collect();
+    let rebuilt = apply(&old, &patch).map(|lines| lines.concat());
+    assert_eq!(rebuilt, Ok(new.as_bytes().to_vec()));
+}
+
+/// Turn symbols into a text file, with lines that need special care in ed
+/// scripts and RCS diffs.
+fn text(symbols: &[u8], missing_newline: bool) -> Vec<u8> {
+    const LINES: [&[u8]; 6] = [b"a\n", b".\n", b"..\n", b"b\r\n", b"\n", b"a .\n"];
+    let mut text: Vec<u8> = symbols
+        .iter()
+        .flat_map(|&s| LINES[s as usize % LINES.len()])
+        .copied()
+        .collect();
+    if missing_newline && text.pop().is_some() && text.last() == Some(&b'\r') {
+        text.pop();
+    }
+    text
+}
+
+#[test]
+fn ed_scripts_rebuild_the_new_file() {
+    for_each_case(0x3c6ef372fe94f82b, 2000, |old, new| {
+        let (old, new) = (text(old, false), text(new, false));
+        let script = ed::render(&diff_bytes(&old, &new)).unwrap();
+        assert_eq!(ed::apply(&old, &script), Ok(new), "old: {old:?}");
+    });
+}
+
+#[test]
+fn rcs_scripts_rebuild_the_new_file() {
+    // a separate generator decides which files are missing their final newline
+    let mut rng = Rng(0x853c49e6748fea9b);
+    for_each_case(0xa54ff53a5f1d36f1, 2000, |old, new| {
+        let old = text(old, rng.below(2) == 0);
+        let new = text(new, rng.below(2) == 0);
+        let script = rcs::render(&diff_bytes(&old, &new));
+        assert_eq!(rcs::apply(&old, &script), Ok(new), "old: {old:?}");
+    });
+}
+
+#[test]
+fn scripts_reject_malformed_commands() {
+    assert_eq!(
+        ed::apply(b"a\nb\n", b"3d\n"),
+        Err(ApplyError::InvalidScript { line: 0 })
+    );
+    assert_eq!(
+        ed::apply(b"a\nb\n", b"1a\nc\n"),
+        Err(ApplyError::InvalidScript { line: 0 })
+    );
+    assert_eq!(
+        rcs::apply(b"a\nb\n", b"d2 1\nd1 1\n"),
+        Err(ApplyError::InvalidScript { line: 1 })
+    );
+}
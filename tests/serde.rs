@@ -0,0 +1,95 @@
+#![cfg(feature = "serde")]
+
+use heckel_diff::{apply, diff_str, json, ApplyError, Diff, Patch, TextDiff};
+
+#[test]
+fn diffs_survive_a_round_trip() {
+    let diff = diff_str("a\r\nb\nc\nd", "c\na\r\nd\ne\n");
+    let serialized = serde_json::to_string(&diff).unwrap();
+    let deserialized: TextDiff = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, diff);
+
+    let patch = diff.patch();
+    let serialized = serde_json::to_string(&patch).unwrap();
+    assert_eq!(serde_json::from_str(&serialized).ok(), Some(patch));
+}
+
+#[test]
+fn json_output_follows_the_schema() {
+    let diff = diff_str("a\nb\nc\n", "c\na\nd\n");
+    let output = json::render(&diff, &json::Options::new("old.txt", "new.txt"));
+    let actual: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let expected = serde_json::json!({
+        "version": json::VERSION,
+        "old": { "label": "old.txt", "lines": 3, "missing_newline": false },
+        "new": { "label": "new.txt", "lines": 3, "missing_newline": false },
+        "identical": false,
+        "operations": [
+            { "kind": "delete", "old": [1, 2], "new": [0, 0], "lines": ["b\n"] },
+            { "kind": "equal", "old": [2, 3], "new": [0, 1], "lines": ["c\n"] },
+            { "kind": "move", "old": [0, 1], "new": [1, 2], "lines": ["a\n"] },
+            { "kind": "insert", "old": [3, 3], "new": [2, 3], "lines": ["d\n"] }
+        ],
+        "moves": [{ "old": [0, 1], "new": [1, 2] }]
+    });
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn malformed_patches_fail_to_apply() {
+    // ranges past the end of the old file must be reported, not panic
+    let equal = r#"{"old_len": 2, "ops": [{"Equal": {"old": {"start": 1, "end": 3}, "lines": ["b", "c"]}}]}"#;
+    let patch: Patch<&str> = serde_json::from_str(equal).unwrap();
+    assert_eq!(
+        apply(&["a", "b"], &patch),
+        Err(ApplyError::OutOfBounds { range: 1..3 })
+    );
+
+    let moved =
+        r#"{"old_len": 2, "ops": [{"Move": {"old": {"start": 2, "end": 3}, "lines": ["c"]}}]}"#;
+    let patch: Patch<&str> = serde_json::from_str(moved).unwrap();
+    assert_eq!(
+        apply(&["a", "b"], &patch),
+        Err(ApplyError::OutOfBounds { range: 2..3 })
+    );
+}
+
+#[test]
+fn malformed_diffs_fail_to_deserialize() {
+    let diff = |ops: &str, old_len: usize, new_len: usize| {
+        let json = format!(r#"{{"ops": {ops}, "old_len": {old_len}, "new_len": {new_len}}}"#);
+        serde_json::from_str::<Diff>(&json)
+    };
+    let equal = r#"{"Equal": {"old": {"start": 0, "end": 2}, "new": {"start": 0, "end": 2}}}"#;
+    assert!(diff(&format!("[{equal}]"), 2, 2).is_ok());
+
+    // out of bounds, and not covering every line
+    assert!(diff(&format!("[{equal}]"), 1, 2).is_err());
+    assert!(diff(&format!("[{equal}]"), 3, 2).is_err());
+    assert!(diff(&format!("[{equal}]"), 2, 3).is_err());
+    // the same lines twice
+    assert!(diff(&format!("[{equal}, {equal}]"), 2, 4).is_err());
+    // a move whose ends differ in length
+    let moved = r#"{"Move": {"old": {"start": 0, "end": 2}, "new": {"start": 0, "end": 1}}}"#;
+    assert!(diff(&format!("[{moved}]"), 2, 1).is_err());
+    // lines deleted out of order
+    let delete = |start, end| {
+        format!(r#"{{"Delete": {{"old": {{"start": {start}, "end": {end}}}, "new": 0}}}}"#)
+    };
+    assert!(diff(&format!("[{}, {}]", delete(0, 1), delete(1, 2)), 2, 0).is_ok());
+    assert!(diff(&format!("[{}, {}]", delete(1, 2), delete(0, 1)), 2, 0).is_err());
+}
+
+#[test]
+fn text_diffs_must_fit_their_texts() {
+    let diff = diff_str("a\nb\n", "b\nc\n");
+    let mut value = serde_json::to_value(&diff).unwrap();
+    assert!(serde_json::from_value::<TextDiff>(value.clone()).is_ok());
+
+    value["new"] = serde_json::to_value(b"b\nc\nd\n").unwrap();
+    let err = serde_json::from_value::<TextDiff>(value).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the diff expects 2 lines in the new file, found 3"
+    );
+}
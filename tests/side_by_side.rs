@@ -0,0 +1,32 @@
+use heckel_diff::{diff_str, side_by_side};
+
+#[test]
+fn moved_lines_are_marked_in_the_gutter() {
+    let diff = diff_str("a\nb\nc\nd\ne\n", "a\nd\ne\nb\nc\nx\n");
+    let options = side_by_side::Options {
+        width: 20,
+        expand_tabs: true,
+        suppress_common_lines: true,
+        ..Default::default()
+    };
+    let output = side_by_side::render(&diff, &options);
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        "b        {\n\
+         c        {\n         }  b\n         }  c\n         >  x\n"
+    );
+}
+
+#[test]
+fn narrow_widths_dont_panic() {
+    // -W 1 and -W 2 match GNU diff, which rejects -W 0 like the CLI does
+    let diff = diff_str("a\nb\n", "a\nc\n");
+    for (width, expected) in [(0, "\n|\n"), (1, " \n|\n"), (2, "  \n| \n")] {
+        let options = side_by_side::Options {
+            width,
+            ..Default::default()
+        };
+        let output = side_by_side::render(&diff, &options);
+        assert_eq!(String::from_utf8_lossy(&output), expected, "width {width}");
+    }
+}
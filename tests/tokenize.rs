@@ -0,0 +1,51 @@
+use heckel_diff::tokenize::{Chars, Graphemes, Lines, TokenDiff, Tokenizer, UnicodeWords, Words};
+use heckel_diff::Op;
+
+/// Split `text` with `tokenizer`, checking the tokens cover all of it.
+fn tokens<'a>(tokenizer: &dyn Tokenizer, text: &'a str) -> Vec<&'a str> {
+    let ranges = tokenizer.tokenize(text.as_bytes());
+    let tokens: Vec<_> = ranges.iter().map(|range| &text[range.clone()]).collect();
+    assert_eq!(tokens.concat(), text);
+    tokens
+}
+
+#[test]
+fn built_in_tokenizers_cover_the_text() {
+    assert_eq!(tokens(&Lines, "a\nb\r\nc"), ["a\n", "b\r\n", "c"]);
+    assert_eq!(
+        tokens(&Words, "don't  panic,\tok"),
+        ["don't", "  ", "panic,", "\t", "ok"]
+    );
+    assert_eq!(
+        tokens(&UnicodeWords, "don't  panic, ok"),
+        ["don't", "  ", "panic", ",", " ", "ok"]
+    );
+    assert_eq!(tokens(&Chars, "e\u{301}é"), ["e", "\u{301}", "é"]);
+    assert_eq!(tokens(&Graphemes, "e\u{301}e🇫🇷"), ["e\u{301}", "e", "🇫🇷"]);
+}
+
+#[test]
+fn invalid_utf8_bytes_are_tokens_of_their_own() {
+    let text = b"ab\xffcd";
+    assert_eq!(UnicodeWords.tokenize(text), [0..2, 2..3, 3..5]);
+    assert_eq!(Graphemes.tokenize(text), [0..1, 1..2, 2..3, 3..4, 4..5]);
+}
+
+#[test]
+fn token_diffs_map_back_to_the_text() {
+    let old = "Heckel's algorithm finds unique lines first.";
+    let new = "Heckel's algorithm finds unique tokens first, then extends.";
+    let diff = TokenDiff::new(old.as_bytes(), new.as_bytes(), &UnicodeWords);
+
+    let mut deleted = Vec::new();
+    let mut inserted = Vec::new();
+    for op in diff.diff().flatten() {
+        match op {
+            Op::Delete { old: tokens, .. } => deleted.push(&old[diff.old_span(tokens)]),
+            Op::Insert { new: tokens, .. } => inserted.push(&new[diff.new_span(tokens)]),
+            _ => {}
+        }
+    }
+    assert_eq!(deleted, ["lines"]);
+    assert_eq!(inserted, ["tokens", ", then extends"]);
+}